@@ -1,21 +1,105 @@
 use hyper::{self, Client, Url};
-use hyper::client::{IntoUrl, Body};
+use hyper::client::{IntoUrl, Body, RedirectPolicy};
 use hyper::client::response::Response;
 use hyper::error::Result;
-use hyper::header::{Authorization, Basic, Header, HeaderFormat, Headers};
+use hyper::header::{Accept, Authorization, Basic, Header, HeaderFormat, Headers,
+                    qitem};
+use hyper::mime::{Mime, SubLevel, TopLevel};
 use hyper::method::Method;
 use hyper::net::HttpsConnector;
+use hyper::status::StatusCode;
 use hyper_rustls::TlsClient;
 use serde_json;
 use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::percent_encoding::percent_decode;
 use std::{fmt, io, error};
 
+use content::{self, Content, FileStatus};
+use mock::MockTransport;
+use package::Package;
+use repository::{Repository, RepositoryType};
+use subject::Subject;
+use utils;
+
 pub struct BintrayClient {
     inner: Client,
+    inner_no_redirect: Client,
     api_base_url: Url,
     dl_base_url: Url,
     username: Option<String>,
     api_key: Option<String>,
+    gpg_passphrase: Option<String>,
+    accept: Mime,
+    correlation_header: String,
+    correlation_id: Option<String>,
+
+    server_info: Mutex<Option<ServerInfo>>,
+    repositories: Mutex<HashMap<(String, String), Repository>>,
+    oss_licenses: Mutex<Option<Vec<String>>>,
+    proprietary_licenses: Mutex<HashMap<String, Vec<String>>>,
+    last_response_headers: Mutex<Option<Headers>>,
+}
+
+/// Informations about the server behind the API, as reported in its
+/// response headers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerInfo {
+    /// The `Server` header.
+    pub server: Option<String>,
+    /// The `X-Bintray-Version` header, if the server sets it. This
+    /// header is not documented: its value is reported as is and is
+    /// not guaranteed to be the version of the API.
+    pub version_header: Option<String>,
+}
+
+/// Health of the Bintray service, as returned by
+/// `BintrayClient::service_status()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServiceStatus {
+    pub health: ServiceHealth,
+    /// Time taken by the probe request.
+    pub latency: Duration,
+}
+
+/// Health levels of the Bintray service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceHealth {
+    /// The API answers normally.
+    Up,
+    /// The API answers with a server error or slowly.
+    Degraded,
+    /// The API answers "503 Service Unavailable".
+    Maintenance,
+}
+
+/// Probe latency above which the service is considered degraded.
+const SLOW_RESPONSE: Duration = Duration::from_secs(5);
+
+/// Outcome of an operation which Bintray may complete asynchronously.
+///
+/// When Bintray replies with "202 Accepted", the operation is still in
+/// progress and the caller should poll for its completion instead of
+/// assuming it is done.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OperationStatus<T> {
+    /// The operation is complete.
+    Done(T),
+    /// The operation was accepted but is still in progress.
+    Pending,
+}
+
+impl<T> OperationStatus<T> {
+    pub fn is_pending(&self) -> bool {
+        match *self {
+            OperationStatus::Pending => true,
+            OperationStatus::Done(_) => false,
+        }
+    }
 }
 
 static BINTRAY_API_BASEURL: &'static str = "https://api.bintray.com/";
@@ -25,6 +109,59 @@ pub enum BintrayError {
     Io(io::Error),
     Http(hyper::Error),
     Json(serde_json::error::Error),
+    /// A subject, repository, package or version name was rejected
+    /// before reaching Bintray.
+    InvalidName { name: String, reason: String },
+    /// The repository is being deleted. This is transient: the
+    /// operation can be retried once the deletion is complete.
+    RepositoryBeingDeleted { repository: String },
+    /// The request conflicts with an existing object, e.g. a file
+    /// uploaded without override.
+    Conflict { object: String, message: String },
+    /// The object requires authentication but the client is anonymous.
+    Unauthorized { object: String, message: String },
+    /// The client sent credentials but Bintray refused them, e.g. an
+    /// invalid or revoked API key.
+    InvalidCredentials { object: String, message: String },
+    /// Bintray accepted the credentials but refused the operation
+    /// (403), e.g. because it requires admin privileges.
+    PermissionDenied { object: String, message: String },
+    /// A file path is absolute or goes up the tree with `..`, so it
+    /// can't be used as is as a remote path.
+    UnsafePath { path: PathBuf },
+    /// The size of a local file changed while it was uploaded, e.g.
+    /// because it was still being written: the uploaded body doesn't
+    /// match the size declared in the request.
+    UploadSizeMismatch { declared: u64, actual: u64 },
+    /// The server failed to handle the request (5xx status). This is
+    /// usually transient: the request can be retried.
+    ServerError { object: String, status: u16, message: String },
+}
+
+impl BintrayError {
+    /// Tells if the error is likely transient, so the operation can be
+    /// retried: a network failure or a server error.
+    ///
+    /// Errors reported by Bintray about the request itself (e.g. not
+    /// found, unauthorized) or about the data (e.g. checksum mismatch)
+    /// are not transient.
+    pub fn is_transient(&self) -> bool {
+        match *self {
+            BintrayError::Http(hyper::Error::Io(_)) => true,
+            BintrayError::Io(ref e) => {
+                matches!(e.kind(),
+                         io::ErrorKind::ConnectionRefused |
+                         io::ErrorKind::ConnectionReset |
+                         io::ErrorKind::ConnectionAborted |
+                         io::ErrorKind::BrokenPipe |
+                         io::ErrorKind::TimedOut |
+                         io::ErrorKind::Interrupted |
+                         io::ErrorKind::UnexpectedEof)
+            }
+            BintrayError::ServerError { .. } => true,
+            _ => false,
+        }
+    }
 }
 
 impl BintrayClient {
@@ -32,28 +169,140 @@ impl BintrayClient {
                api_key: Option<String>) -> BintrayClient
     {
         let api_base_url = Url::parse(BINTRAY_API_BASEURL).unwrap();
+
+        let client = new_hyper_client(&api_base_url);
+        let client_no_redirect = new_hyper_client(&api_base_url);
+
+        BintrayClient::with_hyper_clients(
+            username, api_key, client, client_no_redirect)
+    }
+
+    /// Creates a client which never reaches Bintray: all requests are
+    /// answered by the given `MockTransport`.
+    ///
+    /// This is meant for examples and tests. See the `mock` module.
+    pub fn mock(username: Option<String>,
+                api_key: Option<String>,
+                transport: MockTransport) -> BintrayClient
+    {
+        let client = Client::with_connector(transport.clone());
+        let client_no_redirect = Client::with_connector(transport);
+
+        BintrayClient::with_hyper_clients(
+            username, api_key, client, client_no_redirect)
+    }
+
+    fn with_hyper_clients(username: Option<String>,
+                          api_key: Option<String>,
+                          client: Client,
+                          mut client_no_redirect: Client) -> BintrayClient
+    {
+        let api_base_url = Url::parse(BINTRAY_API_BASEURL).unwrap();
         let dl_base_url = Url::parse(BINTRAY_DL_BASEURL).unwrap();
         assert_eq!(api_base_url.scheme(), dl_base_url.scheme());
 
-        /* We need to setup a TLS client because we'll use HTTPS. */
-        let client = match api_base_url.scheme() {
-            "https" => {
-                let ssl = TlsClient::new();
-                let connector = HttpsConnector::new(ssl);
-                Client::with_connector(connector)
-            }
-            _ => {
-                Client::new()
-            }
-        };
+        client_no_redirect.set_redirect_policy(RedirectPolicy::FollowNone);
 
         BintrayClient {
             inner: client,
+            inner_no_redirect: client_no_redirect,
             api_base_url: api_base_url,
             dl_base_url: dl_base_url,
             username: username,
             api_key: api_key,
+            gpg_passphrase: None,
+            accept: Mime(TopLevel::Application, SubLevel::Json, vec![]),
+            correlation_header: String::from("X-Request-Id"),
+            correlation_id: None,
+
+            server_info: Mutex::new(None),
+            repositories: Mutex::new(HashMap::new()),
+            oss_licenses: Mutex::new(None),
+            proprietary_licenses: Mutex::new(HashMap::new()),
+            last_response_headers: Mutex::new(None),
+        }
+    }
+
+    /// Sets the passphrase of the GPG key used by Bintray to sign
+    /// versions and repository metadata.
+    ///
+    /// It is only sent to the signing and metadata calculation
+    /// endpoints, never with regular uploads or downloads.
+    pub fn set_gpg_passphrase(mut self, gpg_passphrase: &str)
+        -> BintrayClient
+    {
+        self.gpg_passphrase = Some(String::from(gpg_passphrase));
+        self
+    }
+
+    /// Sets a correlation ID sent with every request, so the requests
+    /// of a pipeline can be matched in the logs of other services.
+    ///
+    /// It is sent in the `X-Request-Id` header, unless another name
+    /// is set with `set_correlation_header()`. The ID must be made of
+    /// printable ASCII characters: control characters would allow to
+    /// inject other headers.
+    pub fn set_correlation_id(mut self, correlation_id: &str)
+        -> ::std::result::Result<BintrayClient, BintrayError>
+    {
+        if !correlation_id.bytes().all(|b| (b' '..=b'~').contains(&b)) {
+            let error = io::Error::new(io::ErrorKind::InvalidInput, format!(
+                    "Bintray::SetCorrelationId: Invalid header value {:?}",
+                    correlation_id));
+            return Err(BintrayError::from(error));
         }
+
+        self.correlation_id = Some(String::from(correlation_id));
+        Ok(self)
+    }
+
+    /// Sets the name of the header carrying the correlation ID. It must
+    /// be a valid HTTP header name.
+    pub fn set_correlation_header(mut self, name: &str)
+        -> ::std::result::Result<BintrayClient, BintrayError>
+    {
+        if !is_header_name(name) {
+            let error = io::Error::new(io::ErrorKind::InvalidInput, format!(
+                    "Bintray::SetCorrelationHeader: Invalid header name {:?}",
+                    name));
+            return Err(BintrayError::from(error));
+        }
+
+        self.correlation_header = String::from(name);
+        Ok(self)
+    }
+
+    /// Sets the `Accept` header sent with API requests, to pin or try
+    /// another version of the API. It defaults to `application/json`.
+    ///
+    /// The header is not sent with downloads.
+    pub fn set_accept(mut self, accept: &str)
+        -> ::std::result::Result<BintrayClient, BintrayError>
+    {
+        self.accept = accept.parse().map_err(|()| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!(
+                    "Bintray::SetAccept: Invalid media type \"{}\"",
+                    accept))
+        })?;
+        Ok(self)
+    }
+
+    /// Sets how redirections are followed. By default, they are all
+    /// followed.
+    ///
+    /// Bintray may redirect downloads to a CDN. This allows to not
+    /// follow redirections to hosts which are not allowed on the
+    /// network for instance.
+    pub fn set_redirect_policy(mut self, policy: RedirectPolicy)
+        -> BintrayClient
+    {
+        self.inner.set_redirect_policy(policy);
+        self
+    }
+
+    /// Returns true if the client sends credentials with its requests.
+    pub fn is_authenticated(&self) -> bool {
+        self.username.is_some()
     }
 
     pub fn get_base_url(&self) -> Url {
@@ -64,34 +313,714 @@ impl BintrayClient {
         self.dl_base_url.clone()
     }
 
-    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    /// Returns informations about the server behind the API, taken
+    /// from the headers of a response to the root of the API.
+    ///
+    /// This allows to adapt to or warn about differences between
+    /// Bintray-compatible backends. A successful result is cached:
+    /// only the first successful call queries the server.
+    pub fn server_info(&self)
+        -> ::std::result::Result<ServerInfo, BintrayError>
+    {
+        if let Some(ref server_info) = *self.server_info.lock().unwrap() {
+            return Ok(server_info.clone());
+        }
+
+        let url = self.get_base_url();
+        let mut resp = self.get(url.clone()).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status.is_success() => {
+                let server_info = ServerInfo {
+                    server: utils::get_raw_header(&resp.headers, "Server"),
+                    version_header: utils::get_raw_header(
+                        &resp.headers, "X-Bintray-Version"),
+                };
+                info!("GetServerInfo({}): {:?}", url, server_info);
+
+                *self.server_info.lock().unwrap() = Some(server_info.clone());
+                Ok(server_info)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    url, resp, body, "GetServerInfo", self)
+            }
+            _ => {
+                report_bintray_error!(
+                    url, resp, body, "GetServerInfo",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Returns the headers of the last response received by this
+    /// client, whatever the operation and its status.
+    ///
+    /// This exposes headers the crate doesn't model (request ID,
+    /// rate limiting, etc.) to diagnose odd behaviors. It is best
+    /// effort: when the client is shared between threads, the headers
+    /// may belong to a request made by another thread.
+    pub fn last_response_headers(&self) -> Option<Headers> {
+        self.last_response_headers.lock().unwrap().clone()
+    }
+
+    /// Tells if the Bintray service is healthy, e.g. to pause a release
+    /// while it is not.
+    ///
+    /// Bintray has no status endpoint: the root of the API is queried
+    /// instead, like `server_info()` does, and the response is
+    /// interpreted. A "503 Service Unavailable" means maintenance; any
+    /// other server error, or a response slower than 5 seconds, means
+    /// degraded. If the API can't be reached at all, the error is
+    /// returned.
+    pub fn service_status(&self)
+        -> ::std::result::Result<ServiceStatus, BintrayError>
+    {
+        let url = self.get_base_url();
+
+        let start = Instant::now();
+        let mut resp = self.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+        let latency = start.elapsed();
+
+        let health = match resp.status {
+            StatusCode::ServiceUnavailable   => ServiceHealth::Maintenance,
+            status if status.is_server_error() => ServiceHealth::Degraded,
+            _ if latency > SLOW_RESPONSE     => ServiceHealth::Degraded,
+            _                                => ServiceHealth::Up,
+        };
+
+        let service_status = ServiceStatus { health, latency };
+        info!("GetServiceStatus: {} -> {:?}",
+              format_status_line!(resp), service_status);
+        Ok(service_status)
+    }
+
+    /// Returns the SHA-256 checksum of a published file, or `None` if
+    /// the file doesn't exist.
+    ///
+    /// Only a HEAD request on the download URL is issued, so this is
+    /// cheaper than going through a `Content`.
+    pub fn content_checksum<T: AsRef<Path>>(&self,
+                                            owner: &str,
+                                            repository: &str,
+                                            path: T)
+        -> ::std::result::Result<Option<String>, BintrayError>
+    {
+        let cleaned_path = content::clean_path(path);
+
+        let mut url = self.get_dl_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.extend(&[owner, repository]);
+            path.extend(cleaned_path.iter().map(|v| v.to_string_lossy()));
+        }
+
+        let object = format!("{}/{}/{}",
+                             owner, repository, cleaned_path.display());
+
+        let resp = self.head(url)
+            .send()?;
+
+        let body = String::new();
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("GetContentChecksum({}): Ok", object);
+                Ok(content::checksum_from_response(&resp))
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                info!("GetContentChecksum({}): Not found", object);
+                Ok(None)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    object, resp, body, "GetContentChecksum", self)
+            }
+            _ => {
+                report_bintray_error!(
+                    object, resp, body, "GetContentChecksum",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Downloads a file of the latest version of a package to
+    /// `dest_dir` and returns its local path.
+    ///
+    /// The latest version is determined by the package's version sort
+    /// strategy. The file is the only one whose name matches
+    /// `file_glob` (see `utils::glob_match()`); the glob is matched
+    /// against the whole path if it contains a `/`. The downloaded file
+    /// is verified against the SHA-1 checksum reported by Bintray.
+    pub fn download_latest(&self,
+                           owner: &str,
+                           repository: &str,
+                           package: &str,
+                           file_glob: &str,
+                           dest_dir: &Path)
+        -> ::std::result::Result<PathBuf, BintrayError>
+    {
+        let object = format!("{}/{}/{}", owner, repository, package);
+        let fail = |kind: io::ErrorKind, msg: String| {
+            error!("DownloadLatest({}): {}", object, msg);
+            let error = io::Error::new(
+                kind, format!("Bintray::DownloadLatest({}): {}", object, msg));
+            Err(BintrayError::from(error))
+        };
+
+        let mut package = Package::new(owner, repository, package);
+        package.get(false, self)?;
+
+        let version = match package.get_versions(None).pop() {
+            Some(version) => version,
+            None => {
+                return fail(io::ErrorKind::NotFound,
+                            String::from("Package has no versions"));
+            }
+        };
+
+        let mut files: Vec<Content> = version.list_files(false, self)?
+            .into_iter()
+            .filter(|file| {
+                let name = if file_glob.contains('/') {
+                    file.path.to_string_lossy()
+                } else {
+                    file.path.file_name()
+                        .map(|name| name.to_string_lossy())
+                        .unwrap_or_default()
+                };
+                utils::glob_match(file_glob, &name)
+            })
+            .collect();
+
+        let file = match files.len() {
+            1 => files.remove(0),
+            0 => {
+                return fail(io::ErrorKind::NotFound, format!(
+                        "No file matching \"{}\" in version {}",
+                        file_glob, version.version));
+            }
+            _ => {
+                return fail(io::ErrorKind::InvalidInput, format!(
+                        "Several files matching \"{}\" in version {}",
+                        file_glob, version.version));
+            }
+        };
+
+        let local_filename = match file.path.file_name() {
+            Some(name) => dest_dir.join(name),
+            None => {
+                return fail(io::ErrorKind::InvalidData, format!(
+                        "Invalid file path {}", file.path.display()));
+            }
+        };
+
+        file.download_verified(&local_filename, self)?;
+
+        info!("DownloadLatest({}): {} downloaded to {}",
+              object, file, local_filename.display());
+        Ok(local_filename)
+    }
+
+    /// Returns the status of each file, in the same order as `files`.
+    ///
+    /// Up to `concurrency` files are checked in parallel, each one with
+    /// `Content::status()`. This is useful to validate a release in one
+    /// call. The first error is returned, once all files are checked.
+    pub fn check_files(&self, files: &[Content], concurrency: usize)
+        -> ::std::result::Result<Vec<(PathBuf, FileStatus)>, BintrayError>
+    {
+        utils::parallel_map(files, concurrency, |file| {
+            file.status(self).map(|status| (file.path.clone(), status))
+        })
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns a `Content` from its coordinates, without going through
+    /// `Repository`, `Package` and `Version` structures.
+    ///
+    /// If `repository_type` is `None`, the repository type and YUM
+    /// settings are taken from `get_repository()`, like
+    /// `Content::with_repository()`.
+    pub fn content<T: AsRef<Path>>(&self,
+                                   owner: &str,
+                                   repository: &str,
+                                   package: &str,
+                                   version: &str,
+                                   path: T,
+                                   repository_type: Option<&RepositoryType>)
+        -> ::std::result::Result<Content, BintrayError>
+    {
+        match repository_type {
+            Some(type_) => {
+                let mut content =
+                    Content::new(owner, repository, package, version, path);
+                content.repository_type = match *type_ {
+                    RepositoryType::Deb => Some(RepositoryType::Debian),
+                    ref type_           => Some(type_.clone()),
+                };
+                Ok(content)
+            }
+            None => {
+                let repository = self.get_repository(owner, repository)?;
+                Ok(Content::with_repository(&repository, package, version, path))
+            }
+        }
+    }
+
+    /// Returns the attributes of a repository.
+    ///
+    /// The repository is queried the first time only: the result is
+    /// cached by this client. This is used to learn the type and
+    /// settings of a repository when they are needed, e.g. by
+    /// `Content::index_urls()`.
+    pub fn get_repository(&self, owner: &str, repository: &str)
+        -> ::std::result::Result<Repository, BintrayError>
+    {
+        let key = (String::from(owner), String::from(repository));
+        if let Some(cached) = self.repositories.lock().unwrap().get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let mut queried = Repository::new(owner, repository);
+        queried.get(self)?;
+
+        self.repositories.lock().unwrap().insert(key, queried.clone());
+        Ok(queried)
+    }
+
+    /// Same as `get_repository()`, but always queries Bintray, even if
+    /// the repository is cached.
+    ///
+    /// The result replaces the cached repository, so later
+    /// calls to `get_repository()` and `get_repository_type()`, and
+    /// `Content` methods relying on them, see it too. Other caches
+    /// (`server_info()`, `oss_licenses()`, `proprietary_licenses()`) are
+    /// left alone.
+    /// `Repository::get()` itself never uses a cache.
+    pub fn get_repository_fresh(&self, owner: &str, repository: &str)
+        -> ::std::result::Result<Repository, BintrayError>
+    {
+        let key = (String::from(owner), String::from(repository));
+
+        let mut queried = Repository::new(owner, repository);
+        if let Err(e) = queried.get(self) {
+            self.forget_repository(owner, repository);
+            return Err(e);
+        }
+
+        self.repositories.lock().unwrap().insert(key, queried.clone());
+        Ok(queried)
+    }
+
+    /// Returns the type of a repository.
+    ///
+    /// The type is taken from `get_repository()`, so it shares its
+    /// cache: the repository is queried only if it is not cached
+    /// already. The `Deb` type is reported as `Debian`. To query the
+    /// type alone without caching it, use `Repository::get_type()`.
+    pub fn get_repository_type(&self, owner: &str, repository: &str)
+        -> ::std::result::Result<RepositoryType, BintrayError>
+    {
+        match self.get_repository(owner, repository)?.type_ {
+            RepositoryType::Deb => Ok(RepositoryType::Debian),
+            type_               => Ok(type_),
+        }
+    }
+
+    /// Drops a repository from the cache filled by `get_repository()`.
+    pub fn forget_repository(&self, owner: &str, repository: &str) {
+        let key = (String::from(owner), String::from(repository));
+        self.repositories.lock().unwrap().remove(&key);
+    }
+
+    /// Empties the cache filled by `get_repository()`.
+    ///
+    /// This is only needed if repositories were changed by another
+    /// client, e.g. deleted and recreated with a different type.
+    pub fn clear_repository_cache(&self) {
+        self.repositories.lock().unwrap().clear();
+    }
+
+    /// Returns the `Content` downloadable from the given URL, as found
+    /// in download links (e.g.
+    /// `https://dl.bintray.com/my-company/my-repo/path/to/file`).
+    ///
+    /// The URL must point to the download server of this client. As the
+    /// package and version are not part of the URL, they are queried
+    /// from Bintray.
+    pub fn content_from_url(&self, url: &Url)
+        -> ::std::result::Result<Content, BintrayError>
+    {
+        let invalid = |msg: &str| {
+            error!("ContentFromUrl({}): {}", url, msg);
+            let error = io::Error::new(
+                io::ErrorKind::InvalidInput, format!(
+                    "Bintray::ContentFromUrl({}): {}", url, msg));
+            Err(BintrayError::from(error))
+        };
+
+        let dl_base_url = self.get_dl_base_url();
+        if url.scheme() != dl_base_url.scheme() ||
+            url.host_str() != dl_base_url.host_str() ||
+            url.port_or_known_default() != dl_base_url.port_or_known_default()
+        {
+            return invalid("Not a URL of the download server");
+        }
+
+        let segments: Vec<String> = match url.path_segments() {
+            Some(segments) => {
+                segments
+                    .map(|segment| {
+                        percent_decode(segment.as_bytes())
+                            .decode_utf8_lossy()
+                            .into_owned()
+                    })
+                    .collect()
+            }
+            None => vec![],
+        };
+        if segments.len() < 3 || segments.iter().any(String::is_empty) {
+            return invalid("Expected a URL of the form <owner>/<repo>/<path>");
+        }
+        let (owner, repository) = (&segments[0], &segments[1]);
+        let path = segments[2..].join("/");
+
+        let mut api_url = self.get_base_url();
+        {
+            let mut api_path = api_url.path_segments_mut().unwrap();
+            api_path.extend(&["file_version", owner, repository]);
+            api_path.extend(&segments[2..]);
+        }
+
+        #[derive(Deserialize)]
+        struct GetFileVersionResp {
+            package: String,
+            name: String,
+        };
+
+        let object = format!("{}/{}/{}", owner, repository, path);
+
+        let mut resp = self.get(api_url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("ContentFromUrl({}): {}", object, body);
+
+                let version: GetFileVersionResp =
+                    serde_json::from_str(&body)?;
+
+                Ok(Content::new(owner, repository,
+                                &version.package, &version.name,
+                                &path))
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    object, resp, body, "ContentFromUrl",
+                    io::ErrorKind::NotFound,
+                    "File not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    object, resp, body, "ContentFromUrl", self)
+            }
+            _ => {
+                report_bintray_error!(
+                    object, resp, body, "ContentFromUrl",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Returns the subjects the authenticated user has access to: the
+    /// user itself and the organizations it is a member of.
+    ///
+    /// The list is sorted. It is empty for an anonymous client.
+    pub fn accessible_subjects(&self)
+        -> ::std::result::Result<Vec<String>, BintrayError>
+    {
+        let username = match self.username {
+            Some(ref username) => username,
+            None => return Ok(vec![]),
+        };
+
+        let mut url = self.get_base_url();
+        url.path_segments_mut().unwrap().extend(&["users", username]);
+
+        #[derive(Deserialize)]
+        struct GetUserResp {
+            #[serde(default)]
+            organizations: Vec<String>,
+        }
+
+        let mut resp = self.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("GetAccessibleSubjects({}): {}", username, body);
+
+                let user: GetUserResp = serde_json::from_str(&body)?;
+
+                let mut subjects = user.organizations;
+                subjects.push(username.clone());
+                subjects.sort();
+                subjects.dedup();
+                Ok(subjects)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    username, resp, body, "GetAccessibleSubjects",
+                    io::ErrorKind::NotFound,
+                    "User not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    username, resp, body, "GetAccessibleSubjects", self)
+            }
+            _ => {
+                report_bintray_error!(
+                    username, resp, body, "GetAccessibleSubjects",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Returns the names of the open source licenses accepted by
+    /// Bintray, e.g. `Apache-2.0`.
+    ///
+    /// The list rarely changes, so the result is cached: only the first
+    /// call queries the server.
+    pub fn oss_licenses(&self)
+        -> ::std::result::Result<Vec<String>, BintrayError>
+    {
+        if let Some(ref licenses) = *self.oss_licenses.lock().unwrap() {
+            return Ok(licenses.clone());
+        }
+
+        let mut url = self.get_base_url();
+        url.path_segments_mut().unwrap().extend(&["licenses", "oss_licenses"]);
+
+        let licenses = self.get_licenses(url, "Bintray", "GetOssLicenses")?;
+
+        *self.oss_licenses.lock().unwrap() = Some(licenses.clone());
+        Ok(licenses)
+    }
+
+    /// Returns the names of the proprietary licenses defined by a user
+    /// or an organization.
+    ///
+    /// Like `oss_licenses()`, the result is cached, per subject: only
+    /// the first call for a subject queries the server. Licenses
+    /// defined afterwards are only seen by a new client.
+    pub fn proprietary_licenses(&self, subject: &str)
+        -> ::std::result::Result<Vec<String>, BintrayError>
+    {
+        if let Some(licenses) = self.proprietary_licenses.lock().unwrap()
+            .get(subject) {
+            return Ok(licenses.clone());
+        }
+
+        let mut subject = Subject::new(subject);
+        let kind = subject.kind(self)?;
+
+        let mut url = self.get_base_url();
+        url.path_segments_mut().unwrap()
+            .extend(&[kind.endpoint(), &subject.name, "licenses"]);
+
+        let licenses = self.get_licenses(
+            url, &subject.name, "GetProprietaryLicenses")?;
+
+        self.proprietary_licenses.lock().unwrap()
+            .insert(subject.name.clone(), licenses.clone());
+        Ok(licenses)
+    }
+
+    /// Returns true if `license` is an open source license accepted by
+    /// Bintray, as listed by `oss_licenses()`.
+    ///
+    /// This allows to validate a package's licenses before creating it.
+    pub fn is_valid_license(&self, license: &str)
+        -> ::std::result::Result<bool, BintrayError>
+    {
+        Ok(self.oss_licenses()?.iter().any(|name| name == license))
+    }
+
+    fn get_licenses(&self, url: Url, object: &str, function: &str)
+        -> ::std::result::Result<Vec<String>, BintrayError>
+    {
+        #[derive(Deserialize)]
+        struct GetLicensesResp {
+            name: String,
+        }
+
+        let mut resp = self.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("{}({}): {}", function, object, body);
+
+                let result: Vec<GetLicensesResp> =
+                    serde_json::from_str(&body)?;
+
+                Ok(result.into_iter().map(|item| item.name).collect())
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    object, resp, body, function, self)
+            }
+            _ => {
+                report_bintray_error!(
+                    object, resp, body, function,
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Sends a request built with `get()`, `post()`, etc. and returns
+    /// the response if its status is a success (2xx), for the caller to
+    /// read.
+    ///
+    /// Otherwise, the body is read and an error is returned, the same
+    /// way as the other API calls: `BintrayError::Unauthorized` or
+    /// `BintrayError::InvalidCredentials` for a 401,
+    /// `BintrayError::Conflict` for a 409, and an `io::Error` with a
+    /// kind matching the status for others. This is useful to call
+    /// endpoints not covered by this crate.
+    pub fn send(&self, request: RequestBuilder)
+        -> ::std::result::Result<Response, BintrayError>
+    {
+        let object = format!("{} {}", request.method, request.url.path());
+
+        let mut resp = request.send()?;
+        if resp.status.is_success() {
+            info!("Send({}): {}", object, format_status_line!(resp));
+            return Ok(resp);
+        }
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    object, resp, body, "Send",
+                    io::ErrorKind::NotFound,
+                    "Not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    object, resp, body, "Send", self)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
+                report_bintray_error!(
+                    object, resp, body, "Send",
+                    io::ErrorKind::PermissionDenied,
+                    "Permission denied")
+            }
+            _ if resp.status == StatusCode::Conflict => {
+                error!("Send({}): {}\n{}",
+                       object, format_status_line!(resp),
+                       utils::prettify_json(&body));
+                Err(BintrayError::Conflict {
+                    message: utils::get_bintray_error_message(
+                        body, resp.status, "Conflict"),
+                    object,
+                })
+            }
+            _ => {
+                report_bintray_error!(
+                    object, resp, body, "Send",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
         self.request(Method::Get, url)
     }
 
-    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    pub fn head<U: IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
+        self.request(Method::Head, url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
         self.request(Method::Post, url)
     }
 
-    pub fn put<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    pub fn put<U: IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
         self.request(Method::Put, url)
     }
 
-    pub fn patch<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    pub fn patch<U: IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
         self.request(Method::Patch, url)
     }
 
-    pub fn delete<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+    pub fn delete<U: IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
         self.request(Method::Delete, url)
     }
 
-    pub fn request<U: IntoUrl>(&self, method: Method, url: U) -> RequestBuilder {
+    pub fn request<U: IntoUrl>(&self, method: Method, url: U)
+        -> RequestBuilder<'_>
+    {
+        self.request_with(&self.inner, method, url)
+    }
+
+    /// Same as `request()` but redirections are never followed,
+    /// whatever the redirect policy.
+    pub fn request_without_redirect<U: IntoUrl>(&self, method: Method, url: U)
+        -> RequestBuilder<'_>
+    {
+        self.request_with(&self.inner_no_redirect, method, url)
+    }
+
+    fn request_with<'a, U: IntoUrl>(&'a self,
+                                    inner: &'a Client,
+                                    method: Method,
+                                    url: U)
+        -> RequestBuilder<'a>
+    {
         let final_url = url.into_url().unwrap();
 
-        let request = self.inner.request(method.clone(), final_url.clone());
+        let mut request = inner.request(method.clone(), final_url.clone());
+        if final_url.host_str() == self.api_base_url.host_str() {
+            let accept = Accept(vec![qitem(self.accept.clone())]);
+            request = request.header(accept);
+        }
+        if let Some(ref correlation_id) = self.correlation_id {
+            let mut headers = Headers::new();
+            headers.set_raw(self.correlation_header.clone(),
+                            vec![correlation_id.clone().into_bytes()]);
+            request = request.headers(headers);
+        }
+
         RequestBuilder {
             inner: request,
             username: self.username.clone(),
             password: self.api_key.clone(),
+            gpg_passphrase: self.gpg_passphrase.clone(),
+            last_response_headers: &self.last_response_headers,
 
             method: method,
             url: final_url,
@@ -99,6 +1028,40 @@ impl BintrayClient {
     }
 }
 
+/// Tells if `name` is a token, as defined by RFC 7230, and thus a valid
+/// header name.
+fn is_header_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| {
+        b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+    })
+}
+
+fn new_hyper_client(base_url: &Url) -> Client {
+    /* We need to setup a TLS client because we'll use HTTPS. */
+    match base_url.scheme() {
+        "https" => {
+            let ssl = TlsClient::new();
+            let connector = HttpsConnector::new(ssl);
+            Client::with_connector(connector)
+        }
+        _ => {
+            Client::new()
+        }
+    }
+}
+
+impl fmt::Debug for BintrayClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The API key and the GPG passphrase are secrets: they are
+        // left out on purpose.
+        f.debug_struct("BintrayClient")
+            .field("api_base_url", &self.api_base_url.as_str())
+            .field("dl_base_url", &self.dl_base_url.as_str())
+            .field("username", &self.username)
+            .finish()
+    }
+}
+
 impl From<io::Error> for BintrayError {
     fn from(error: io::Error) -> BintrayError {
         BintrayError::Io(error)
@@ -123,6 +1086,34 @@ impl fmt::Debug for BintrayError {
             BintrayError::Io(ref e) => write!(f, "{:?}", e),
             BintrayError::Http(ref e) => write!(f, "{:?}", e),
             BintrayError::Json(ref e) => write!(f, "{:?}", e),
+            BintrayError::InvalidName { ref name, ref reason } => {
+                write!(f, "InvalidName({:?}, {:?})", name, reason)
+            }
+            BintrayError::RepositoryBeingDeleted { ref repository } => {
+                write!(f, "RepositoryBeingDeleted({:?})", repository)
+            }
+            BintrayError::Conflict { ref object, ref message } => {
+                write!(f, "Conflict({:?}, {:?})", object, message)
+            }
+            BintrayError::Unauthorized { ref object, ref message } => {
+                write!(f, "Unauthorized({:?}, {:?})", object, message)
+            }
+            BintrayError::InvalidCredentials { ref object, ref message } => {
+                write!(f, "InvalidCredentials({:?}, {:?})", object, message)
+            }
+            BintrayError::PermissionDenied { ref object, ref message } => {
+                write!(f, "PermissionDenied({:?}, {:?})", object, message)
+            }
+            BintrayError::UnsafePath { ref path } => {
+                write!(f, "UnsafePath({:?})", path)
+            }
+            BintrayError::UploadSizeMismatch { declared, actual } => {
+                write!(f, "UploadSizeMismatch({}, {})", declared, actual)
+            }
+            BintrayError::ServerError { ref object, status, ref message } => {
+                write!(f, "ServerError({:?}, {}, {:?})",
+                       object, status, message)
+            }
         }
     }
 }
@@ -133,6 +1124,36 @@ impl fmt::Display for BintrayError {
             BintrayError::Io(ref e) => write!(f, "I/O error: {}", e),
             BintrayError::Http(ref e) => write!(f, "HTTP error: {}", e),
             BintrayError::Json(ref e) => write!(f, "JSON error: {}", e),
+            BintrayError::InvalidName { ref name, ref reason } => {
+                write!(f, "Invalid name \"{}\": {}", name, reason)
+            }
+            BintrayError::RepositoryBeingDeleted { ref repository } => {
+                write!(f, "Repository {} is being deleted", repository)
+            }
+            BintrayError::Conflict { ref object, ref message } => {
+                write!(f, "Conflict on {}: {}", object, message)
+            }
+            BintrayError::Unauthorized { ref object, ref message } => {
+                write!(f, "Authentication required for {}: {}",
+                       object, message)
+            }
+            BintrayError::InvalidCredentials { ref object, ref message } => {
+                write!(f, "Credentials refused for {}: {}", object, message)
+            }
+            BintrayError::PermissionDenied { ref object, ref message } => {
+                write!(f, "Permission denied on {}: {}", object, message)
+            }
+            BintrayError::UnsafePath { ref path } => {
+                write!(f, "Unsafe path \"{}\": absolute or containing \"..\"",
+                       path.display())
+            }
+            BintrayError::UploadSizeMismatch { declared, actual } => {
+                write!(f, "File size changed during upload: {} bytes \
+                           declared, {} bytes found", declared, actual)
+            }
+            BintrayError::ServerError { ref object, status, ref message } => {
+                write!(f, "Server error {} on {}: {}", status, object, message)
+            }
         }
     }
 }
@@ -143,6 +1164,17 @@ impl error::Error for BintrayError {
             BintrayError::Io(ref e) => e.description(),
             BintrayError::Http(ref e) => e.description(),
             BintrayError::Json(ref e) => e.description(),
+            BintrayError::InvalidName { .. } => "Invalid name",
+            BintrayError::RepositoryBeingDeleted { .. } => {
+                "Repository being deleted"
+            }
+            BintrayError::Conflict { .. } => "Conflict",
+            BintrayError::Unauthorized { .. } => "Authentication required",
+            BintrayError::InvalidCredentials { .. } => "Credentials refused",
+            BintrayError::PermissionDenied { .. } => "Permission denied",
+            BintrayError::UnsafePath { .. } => "Unsafe path",
+            BintrayError::UploadSizeMismatch { .. } => "Upload size mismatch",
+            BintrayError::ServerError { .. } => "Server error",
         }
     }
 
@@ -151,6 +1183,15 @@ impl error::Error for BintrayError {
             BintrayError::Io(ref e) => Some(e),
             BintrayError::Http(ref e) => Some(e),
             BintrayError::Json(ref e) => Some(e),
+            BintrayError::InvalidName { .. } => None,
+            BintrayError::RepositoryBeingDeleted { .. } => None,
+            BintrayError::Conflict { .. } => None,
+            BintrayError::Unauthorized { .. } => None,
+            BintrayError::InvalidCredentials { .. } => None,
+            BintrayError::PermissionDenied { .. } => None,
+            BintrayError::UnsafePath { .. } => None,
+            BintrayError::UploadSizeMismatch { .. } => None,
+            BintrayError::ServerError { .. } => None,
         }
     }
 }
@@ -159,6 +1200,8 @@ pub struct RequestBuilder<'a> {
     inner: hyper::client::RequestBuilder<'a>,
     username: Option<String>,
     password: Option<String>,
+    gpg_passphrase: Option<String>,
+    last_response_headers: &'a Mutex<Option<Headers>>,
 
     method: Method,
     url: Url,
@@ -194,6 +1237,13 @@ impl<'a> RequestBuilder<'a> {
         self
     }
 
+    /// Adds the GPG passphrase configured on the `BintrayClient`, if
+    /// any.
+    pub fn add_client_gpg_passphrase(self) -> RequestBuilder<'a> {
+        let gpg_passphrase = self.gpg_passphrase.clone();
+        self.add_gpg_passphrase(gpg_passphrase.as_deref())
+    }
+
     pub fn add_debian_architecture<T: Borrow<str>>(
         mut self, debian_architecture: &[T])
         -> RequestBuilder<'a>
@@ -254,7 +1304,10 @@ impl<'a> RequestBuilder<'a> {
     pub fn send(self) -> Result<Response> {
         info!("{:?}", self);
 
-        self.add_auth_header().inner.send()
+        let last_response_headers = self.last_response_headers;
+        let resp = self.add_auth_header().inner.send()?;
+        *last_response_headers.lock().unwrap() = Some(resp.headers.clone());
+        Ok(resp)
     }
 }
 
@@ -1,14 +1,31 @@
 use hyper::client::Body;
+use hyper::client::response::Response;
+use hyper::header::{AcceptEncoding, ByteRangeSpec, ContentLength, Encoding,
+                    Headers, Location, Range, qitem};
+use hyper::method::Method;
+use hyper::Url;
 use hyper::status::StatusCode;
 use serde_json;
+use serde_json::{Map, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
 use std::borrow::Borrow;
+use std::cmp;
+use std::collections::HashSet;
 use std::fmt;
-use std::fs::{File, OpenOptions};
-use std::io::{self, Read, BufReader, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, BufReader, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf, Component};
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::time::Duration;
 
 use client::{BintrayClient, BintrayError};
+use entitlement::Entitlement;
+use repository::{Repository, RepositoryType};
 use utils;
+use version::Version;
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -26,6 +43,224 @@ pub struct Content {
     pub size: Option<usize>,
     #[serde(skip_serializing_if="Option::is_none")]
     pub sha1: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub sha256: Option<String>,
+
+    // Repository settings, known when created with
+    // `Content::with_repository()`.
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub repository_type: Option<RepositoryType>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub yum_metadata_depth: Option<u64>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub yum_groups_file: Option<String>,
+
+    /// See `set_override_if_different()`.
+    #[serde(skip_deserializing)]
+    pub override_if_different: bool,
+    /// See `set_checksum_header_algorithm()`.
+    #[serde(skip_deserializing)]
+    pub checksum_header_algorithm: Option<ChecksumAlgorithm>,
+    /// See `set_checksum_buffer_size()`.
+    #[serde(skip_deserializing)]
+    pub checksum_buffer_size: Option<usize>,
+    /// See `set_version_attribute()`.
+    #[serde(skip_deserializing)]
+    pub version_attributes: Vec<(String, String)>,
+    /// See `set_dl_host()`.
+    #[serde(skip_deserializing)]
+    pub dl_host: Option<String>,
+}
+
+/// Size of the buffer used to compute checksums, unless set with
+/// `Content::set_checksum_buffer_size()`.
+pub const DEFAULT_CHECKSUM_BUFFER_SIZE: usize = 65536;
+
+/// Checksums sent as headers along with an upload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    /// `X-Checksum-Sha1` only.
+    Sha1,
+    /// `X-Checksum-Sha2` only.
+    Sha256,
+    /// Both `X-Checksum-Sha1` and `X-Checksum-Sha2`.
+    Both,
+}
+
+/// Options of `Content::upload_with_options()`.
+///
+/// The other upload methods are shortcuts for common combinations of
+/// these options. This allows to define a profile of upload options once and reuse it
+/// across uploads. The default options upload the file without
+/// publishing or overriding it, using the repository default Debian
+/// settings.
+#[derive(Clone, Debug, Default)]
+pub struct UploadOptions {
+    pub publish: bool,
+    pub override_: bool,
+    /// Uploads without override first and retries once with override
+    /// if Bintray reports a conflict, i.e. the remote file exists.
+    /// Beware that the previous content of the remote file is lost.
+    pub override_on_conflict: bool,
+    pub explode: bool,
+    /// Shows the file in the public download list once uploaded. The
+    /// file must be published for this to be accepted.
+    pub show_in_download_list: bool,
+    pub gpg_passphrase: Option<String>,
+    pub debian_architecture: Vec<String>,
+    pub debian_distribution: Vec<String>,
+    pub debian_component: Vec<String>,
+    /// Aborts the transfer with an error as soon as it is set, e.g.
+    /// from another thread on shutdown.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl UploadOptions {
+    pub fn new() -> UploadOptions {
+        UploadOptions::default()
+    }
+
+    pub fn set_publish(mut self, publish: bool) -> UploadOptions {
+        self.publish = publish;
+        self
+    }
+
+    pub fn set_override(mut self, override_: bool) -> UploadOptions {
+        self.override_ = override_;
+        self
+    }
+
+    pub fn set_override_on_conflict(mut self, override_on_conflict: bool)
+        -> UploadOptions
+    {
+        self.override_on_conflict = override_on_conflict;
+        self
+    }
+
+    pub fn set_explode(mut self, explode: bool) -> UploadOptions {
+        self.explode = explode;
+        self
+    }
+
+    pub fn set_show_in_download_list(mut self, show: bool) -> UploadOptions {
+        self.show_in_download_list = show;
+        self
+    }
+
+    pub fn set_gpg_passphrase(mut self, gpg_passphrase: &str)
+        -> UploadOptions
+    {
+        self.gpg_passphrase = Some(String::from(gpg_passphrase));
+        self
+    }
+
+    pub fn set_debian_architecture<T: Borrow<str>>(mut self, values: &[T])
+        -> UploadOptions
+    {
+        self.debian_architecture = to_strings(values);
+        self
+    }
+
+    pub fn set_debian_distribution<T: Borrow<str>>(mut self, values: &[T])
+        -> UploadOptions
+    {
+        self.debian_distribution = to_strings(values);
+        self
+    }
+
+    pub fn set_debian_component<T: Borrow<str>>(mut self, values: &[T])
+        -> UploadOptions
+    {
+        self.debian_component = to_strings(values);
+        self
+    }
+
+    pub fn set_cancel(mut self, cancel: Arc<AtomicBool>) -> UploadOptions {
+        self.cancel = Some(cancel);
+        self
+    }
+}
+
+fn to_strings<T: Borrow<str>>(values: &[T]) -> Vec<String> {
+    values.iter().map(|value| String::from(value.borrow())).collect()
+}
+
+/// Returns the options matching the arguments of `Content::upload()`.
+fn positional_options<T: Borrow<str>>(publish: bool,
+                                      override_: bool,
+                                      explode: bool,
+                                      gpg_passphrase: Option<&str>,
+                                      debian_architecture: &[T],
+                                      debian_distribution: &[T],
+                                      debian_component: &[T])
+    -> UploadOptions
+{
+    UploadOptions {
+        publish,
+        override_,
+        explode,
+        gpg_passphrase: gpg_passphrase.map(String::from),
+        debian_architecture: to_strings(debian_architecture),
+        debian_distribution: to_strings(debian_distribution),
+        debian_component: to_strings(debian_component),
+        ..UploadOptions::default()
+    }
+}
+
+/// Result of `Content::upload_with_result()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadResult {
+    /// Path of the file in the repository.
+    pub path: PathBuf,
+    /// False if the server refused the upload because the remote file
+    /// is identical (see `Content::set_override_if_different()`).
+    pub uploaded: bool,
+    /// Number of bytes sent, zero if the file wasn't uploaded.
+    pub size: u64,
+    /// True if the file was uploaded and published in the same request.
+    pub published: bool,
+    /// Warning returned by Bintray, if any.
+    pub warning: Option<String>,
+}
+
+/// Status of a remote file, as returned by `Content::status()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    /// The file can be downloaded. `matches_checksum` tells if its
+    /// SHA-256 checksum is the one in `Content::sha256`; it is false if
+    /// either checksum is unknown.
+    Present { matches_checksum: bool },
+    /// The file doesn't exist or isn't published.
+    Absent,
+}
+
+/// Result of `download_many()`.
+#[derive(Debug, Default)]
+pub struct DownloadReport {
+    /// Local paths of the downloaded files, in the order of the files
+    /// given to `download_many()`.
+    pub downloaded: Vec<PathBuf>,
+    /// Files which couldn't be downloaded, with the last error.
+    pub failed: Vec<(Content, BintrayError)>,
+}
+
+/// Result of `Version::copy_all_to()`.
+#[derive(Debug, Default)]
+pub struct CopyReport {
+    /// Destination paths of the copied files, in the order of the
+    /// source files.
+    pub copied: Vec<PathBuf>,
+    /// Source files which couldn't be copied, with the error.
+    pub failed: Vec<(Content, BintrayError)>,
+}
+
+/// Outcome of an upload request.
+enum UploadOutcome {
+    /// The file was uploaded, possibly with a warning.
+    Uploaded(Option<String>),
+    /// The server refused the upload because the remote file is
+    /// identical.
+    Unchanged,
 }
 
 impl Content {
@@ -37,117 +272,1313 @@ impl Content {
     {
         let cleaned_path = clean_path(path);
 
-        Content {
-            path: cleaned_path,
-            version: String::from(version),
-            package: String::from(package),
-            repository: String::from(repository),
-            owner: String::from(owner),
+        Content {
+            path: cleaned_path,
+            version: String::from(version),
+            package: String::from(package),
+            repository: String::from(repository),
+            owner: String::from(owner),
+
+            created: None,
+            size: None,
+            sha1: None,
+            sha256: None,
+
+            repository_type: None,
+            yum_metadata_depth: None,
+            yum_groups_file: None,
+
+            override_if_different: false,
+            checksum_header_algorithm: None,
+            checksum_buffer_size: None,
+            version_attributes: vec![],
+            dl_host: None,
+        }
+    }
+
+    /// Same as `new()`, but checks names with `utils::validate_name()`
+    /// and refuses paths which `new()` would silently alter.
+    ///
+    /// `new()` strips leading `/`, `.` and `..` components, so the
+    /// remote path may differ from the one requested. Here, an absolute
+    /// path or a path containing `..` is rejected with
+    /// `BintrayError::UnsafePath` instead.
+    pub fn try_new<T: AsRef<Path>>(owner: &str,
+                                   repository: &str,
+                                   package: &str,
+                                   version: &str,
+                                   path: T) -> Result<Content, BintrayError>
+    {
+        utils::validate_name(owner)?;
+        utils::validate_name(repository)?;
+        utils::validate_name(package)?;
+        utils::validate_name(version)?;
+
+        let path = path.as_ref();
+        if is_unsafe_path(path) {
+            error!("NewContent({}): Unsafe path", path.display());
+            return Err(BintrayError::UnsafePath { path: path.to_path_buf() });
+        }
+
+        Ok(Content::new(owner, repository, package, version, path))
+    }
+
+    /// Instanciates a new `Content` structure in an already queried
+    /// repository.
+    ///
+    /// The repository type and YUM settings are copied from
+    /// `repository`, so operations needing them don't have to query
+    /// the repository again.
+    pub fn with_repository<T: AsRef<Path>>(repository: &Repository,
+                                           package: &str,
+                                           version: &str,
+                                           path: T) -> Content
+    {
+        let mut content = Content::new(&repository.owner,
+                                       &repository.repository,
+                                       package,
+                                       version,
+                                       path);
+        content.repository_type = match repository.type_ {
+            RepositoryType::Deb => Some(RepositoryType::Debian),
+            ref type_           => Some(type_.clone()),
+        };
+        content.yum_metadata_depth = repository.yum_metadata_depth;
+        content.yum_groups_file = repository.yum_groups_file.clone();
+        content
+    }
+
+    /// Computes the SHA-1 and SHA-256 checksums of a local file and
+    /// stores them in `sha1` and `sha256`.
+    pub fn set_checksum_from_file<T: AsRef<Path>>(&mut self, local_filename: T)
+        -> Result<&mut Content, BintrayError>
+    {
+        let local_file = File::open(local_filename)?;
+        self.set_checksum_from_reader(local_file)
+    }
+
+    /// Computes the SHA-1 and SHA-256 checksums of the data read from
+    /// `reader` and stores them in `sha1` and `sha256`.
+    ///
+    /// The reader is consumed until EOF.
+    pub fn set_checksum_from_reader<R: Read>(&mut self, mut reader: R)
+        -> Result<&mut Content, BintrayError>
+    {
+        let buffer_size = self.checksum_buffer_size
+            .unwrap_or(DEFAULT_CHECKSUM_BUFFER_SIZE);
+
+        let mut sha1 = Sha1::default();
+        let mut sha256 = Sha256::default();
+        let buffer = &mut vec![0; cmp::max(buffer_size, 1)];
+        loop {
+            let len_read = reader.read(buffer)?;
+            if len_read == 0 {
+                break;
+            }
+
+            sha1.input(&buffer[..len_read]);
+            sha256.input(&buffer[..len_read]);
+        }
+
+        self.sha1 = Some(format!("{:x}", sha1.result()));
+        self.sha256 = Some(format!("{:x}", sha256.result()));
+        Ok(self)
+    }
+
+    /// Sets the size of the buffer used by `set_checksum_from_file()`
+    /// and `set_checksum_from_reader()`.
+    ///
+    /// The default, `DEFAULT_CHECKSUM_BUFFER_SIZE`, is a good fit for
+    /// most files. A larger buffer may speed up checksumming very large
+    /// files.
+    pub fn set_checksum_buffer_size(mut self, size: usize) -> Content {
+        self.checksum_buffer_size = Some(size);
+        self
+    }
+
+    /// Adds an attribute to set on the version when an upload creates
+    /// it.
+    ///
+    /// The attributes are sent in the `X-Bintray-Version-Attributes`
+    /// header, so a version can be tagged (e.g. with a commit SHA) in
+    /// the same request as its first file. Before uploading, the client
+    /// checks if the version exists: the header is sent only if it
+    /// doesn't, i.e. if the upload creates it. Calling this several
+    /// times with the same name gives the attribute several values.
+    pub fn set_version_attribute(mut self, name: &str, value: &str)
+        -> Content
+    {
+        self.version_attributes.push(
+            (String::from(name), String::from(value)));
+        self
+    }
+
+    /// Downloads this file from another host than the client's download
+    /// server, e.g. a CDN serving only some files.
+    ///
+    /// `host` is a URL authority, i.e. a host name with an optional
+    /// port (e.g. `cdn.example.com:8443`). The scheme and the path
+    /// layout of the client's download server are kept. This affects
+    /// downloads, `exists()` and the waits relying on it.
+    pub fn set_dl_host(mut self, host: &str) -> Result<Content, BintrayError> {
+        let is_authority = Url::parse(&format!("https://{}/", host))
+            .map(|url| {
+                let authority = match url.port() {
+                    Some(port) => format!("{}:{}", url.host_str()
+                                          .unwrap_or_default(), port),
+                    None => String::from(url.host_str().unwrap_or_default()),
+                };
+                !host.is_empty() &&
+                    authority.eq_ignore_ascii_case(host) &&
+                    url.path() == "/"
+            })
+            .unwrap_or(false);
+        if !is_authority {
+            error!("SetDownloadHost({}): Invalid host \"{}\"", self, host);
+            let error = io::Error::new(
+                io::ErrorKind::InvalidInput, format!(
+                    "Bintray::SetDownloadHost({}): Invalid host \"{}\"",
+                    self, host));
+            return Err(BintrayError::from(error));
+        }
+
+        self.dl_host = Some(String::from(host));
+        Ok(self)
+    }
+
+    /// Makes uploads override the remote file only if its content
+    /// differs.
+    ///
+    /// The SHA-256 checksum, computed if unknown, is sent along with the
+    /// upload and the server refuses to override an identical file.
+    /// Such a refusal is not reported as an error: `upload()` returns
+    /// `Ok(None)` and `upload_if_changed()` returns `Ok(false)`. Other
+    /// conflicts, e.g. with a published file, are still reported as
+    /// `BintrayError::Conflict`.
+    pub fn set_override_if_different(mut self, override_if_different: bool)
+        -> Content
+    {
+        self.override_if_different = override_if_different;
+        self
+    }
+
+    /// Selects the checksum headers sent along with uploads.
+    ///
+    /// By default, only the SHA-256 checksum is sent, and only when
+    /// `set_override_if_different()` is used. Once an algorithm is
+    /// selected, the corresponding checksums are sent with every upload
+    /// and must be known: see `set_checksum_from_file()`. Otherwise, the
+    /// upload fails without contacting Bintray.
+    pub fn set_checksum_header_algorithm(mut self,
+                                         algorithm: ChecksumAlgorithm)
+        -> Content
+    {
+        self.checksum_header_algorithm = Some(algorithm);
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload<T: Borrow<str>>(&self,
+                                 local_filename: &PathBuf,
+                                 publish: bool,
+                                 override_: bool,
+                                 explode: bool,
+                                 gpg_passphrase: Option<&str>,
+                                 debian_architecture: &[T],
+                                 debian_distribution: &[T],
+                                 debian_component: &[T],
+                                 client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let options = positional_options(
+            publish, override_, explode, gpg_passphrase,
+            debian_architecture, debian_distribution, debian_component);
+        self.upload_with_options(local_filename, &options, client)
+    }
+
+    /// Same as `upload()`, but returns what was uploaded instead of the
+    /// warning only.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload_with_result<T: Borrow<str>>(&self,
+                                             local_filename: &PathBuf,
+                                             publish: bool,
+                                             override_: bool,
+                                             explode: bool,
+                                             gpg_passphrase: Option<&str>,
+                                             debian_architecture: &[T],
+                                             debian_distribution: &[T],
+                                             debian_component: &[T],
+                                             client: &BintrayClient)
+        -> Result<UploadResult, BintrayError>
+    {
+        let options = positional_options(
+            publish, override_, explode, gpg_passphrase,
+            debian_architecture, debian_distribution, debian_component);
+        self.upload_options_result(local_filename, &options, None, client)
+    }
+
+    /// Same as `upload()`, with the options taken from `options`.
+    ///
+    /// The other upload methods are built on this one.
+    pub fn upload_with_options(&self,
+                               local_filename: &PathBuf,
+                               options: &UploadOptions,
+                               client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let result = self.upload_options_result(
+            local_filename, options, None, client)?;
+        Ok(result.warning)
+    }
+
+    /// Uploads `local_filename`, overriding the remote file only if it
+    /// already exists.
+    ///
+    /// The file is first uploaded without override. If Bintray reports
+    /// a conflict, the upload is retried once with override (see
+    /// `UploadOptions::override_on_conflict`). Beware that the previous
+    /// content of the remote file is lost.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload_or_override<T: Borrow<str>>(&self,
+                                             local_filename: &PathBuf,
+                                             publish: bool,
+                                             explode: bool,
+                                             gpg_passphrase: Option<&str>,
+                                             debian_architecture: &[T],
+                                             debian_distribution: &[T],
+                                             debian_component: &[T],
+                                             client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let options = positional_options(
+            publish, false, explode, gpg_passphrase,
+            debian_architecture, debian_distribution, debian_component)
+            .set_override_on_conflict(true);
+        self.upload_with_options(local_filename, &options, client)
+    }
+
+    /// Same as `upload()`, but the transfer is aborted with an error as
+    /// soon as `cancel` is set, e.g. from another thread on shutdown.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload_cancellable<T: Borrow<str>>(&self,
+                                             local_filename: &PathBuf,
+                                             publish: bool,
+                                             override_: bool,
+                                             explode: bool,
+                                             gpg_passphrase: Option<&str>,
+                                             debian_architecture: &[T],
+                                             debian_distribution: &[T],
+                                             debian_component: &[T],
+                                             cancel: &AtomicBool,
+                                             client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let options = positional_options(
+            publish, override_, explode, gpg_passphrase,
+            debian_architecture, debian_distribution, debian_component);
+        let result = self.upload_options_result(
+            local_filename, &options, Some(cancel), client)?;
+        Ok(result.warning)
+    }
+
+    /// Uploads `local_filename` with `options` and returns what was
+    /// uploaded. `cancel`, if set, is used instead of `options.cancel`.
+    fn upload_options_result(&self,
+                             local_filename: &PathBuf,
+                             options: &UploadOptions,
+                             cancel: Option<&AtomicBool>,
+                             client: &BintrayClient)
+        -> Result<UploadResult, BintrayError>
+    {
+        let size = fs::metadata(local_filename)?.len();
+        let result = self.upload_file(local_filename, options, cancel, client);
+        let outcome = match result {
+            Err(BintrayError::Conflict { .. })
+                if options.override_on_conflict && !options.override_ => {
+                info!("UploadContent({}): Remote file exists, overriding",
+                      self);
+                let options = options.clone().set_override(true);
+                self.upload_file(local_filename, &options, cancel, client)?
+            }
+            result => result?,
+        };
+
+        let result = match outcome {
+            UploadOutcome::Uploaded(warning) => {
+                UploadResult {
+                    path: self.path.clone(),
+                    uploaded: true,
+                    size,
+                    published: options.publish,
+                    warning,
+                }
+            }
+            UploadOutcome::Unchanged => {
+                UploadResult {
+                    path: self.path.clone(),
+                    uploaded: false,
+                    size: 0,
+                    published: false,
+                    warning: None,
+                }
+            }
+        };
+        Ok(result)
+    }
+
+    fn upload_file(&self,
+                   local_filename: &PathBuf,
+                   options: &UploadOptions,
+                   cancel: Option<&AtomicBool>,
+                   client: &BintrayClient)
+        -> Result<UploadOutcome, BintrayError>
+    {
+        if self.override_if_different && self.missing_checksum().is_some() {
+            let mut content = self.clone();
+            content.set_checksum_from_file(local_filename)?;
+            return content.upload_file(local_filename, options, cancel, client);
+        }
+
+        let local_file = File::open(local_filename)?;
+        let local_file_size = local_file.metadata()?.len();
+        let mut local_file_reader = SizeCheckingReader {
+            inner: CancellableReader {
+                inner: BufReader::new(local_file),
+                cancel: cancel.or(options.cancel.as_deref()),
+            },
+            declared: local_file_size,
+            read: 0,
+            eof: false,
+        };
+
+        let result = self.upload_body(
+            Body::SizedBody(&mut local_file_reader, local_file_size),
+            options, client);
+
+        if local_file_reader.size_changed() {
+            // The file may have grown past what was read before the
+            // upload was aborted: report its current size if larger.
+            let actual = fs::metadata(local_filename)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            let actual = cmp::max(actual, local_file_reader.read);
+            error!("UploadContent({}): File size changed during upload: \
+                    {} bytes declared, {} bytes found",
+                   self, local_file_size, actual);
+            return Err(BintrayError::UploadSizeMismatch {
+                declared: local_file_size,
+                actual,
+            });
+        }
+
+        self.show_if_uploaded(result?, options, client)
+    }
+
+    fn show_if_uploaded(&self,
+                        outcome: UploadOutcome,
+                        options: &UploadOptions,
+                        client: &BintrayClient)
+        -> Result<UploadOutcome, BintrayError>
+    {
+        if let UploadOutcome::Uploaded(_) = outcome {
+            if options.show_in_download_list {
+                self.show_in_download_list(true, client)?;
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Uploads the body of a response, for instance a download from
+    /// another Bintray repository, without buffering it to disk.
+    ///
+    /// The response `Content-Length`, if any, is used as the size of
+    /// the uploaded body. Otherwise, the body is sent chunked.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload_from_response<T: Borrow<str>>(&self,
+                                               response: Response,
+                                               publish: bool,
+                                               override_: bool,
+                                               explode: bool,
+                                               gpg_passphrase: Option<&str>,
+                                               debian_architecture: &[T],
+                                               debian_distribution: &[T],
+                                               debian_component: &[T],
+                                               client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let options = positional_options(
+            publish, override_, explode, gpg_passphrase,
+            debian_architecture, debian_distribution, debian_component);
+        self.upload_response_with_options(response, &options, client)
+    }
+
+    /// Same as `upload_from_response()`, with the options taken from
+    /// `options`. As the body can't be sent twice,
+    /// `override_on_conflict` is not supported and an
+    /// `io::ErrorKind::InvalidInput` error is returned if it is set.
+    pub fn upload_response_with_options(&self,
+                                        response: Response,
+                                        options: &UploadOptions,
+                                        client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        if options.override_on_conflict && !options.override_ {
+            error!("UploadContent({}): Can't retry the upload of a response",
+                   self);
+            let error = io::Error::new(
+                io::ErrorKind::InvalidInput, format!(
+                    "Bintray::UploadContent({}): \
+                     Can't retry the upload of a response", self));
+            return Err(BintrayError::from(error));
+        }
+
+        let size = response.headers.get::<ContentLength>()
+            .map(|&ContentLength(size)| size);
+        let mut reader = CancellableReader {
+            inner: response,
+            cancel: options.cancel.as_deref(),
+        };
+        let body = match size {
+            Some(size) => Body::SizedBody(&mut reader, size),
+            None       => Body::ChunkedBody(&mut reader),
+        };
+
+        let outcome = self.upload_body(body, options, client)?;
+        let outcome = self.show_if_uploaded(outcome, options, client)?;
+
+        match outcome {
+            UploadOutcome::Uploaded(warning) => Ok(warning),
+            UploadOutcome::Unchanged         => Ok(None),
+        }
+    }
+
+    /// Copies this file to `dest`, e.g. another package or version,
+    /// and returns any warning from the upload.
+    ///
+    /// The file is downloaded and uploaded at the same time with
+    /// `upload_from_response()`, without being buffered to disk. As
+    /// paths are unique across a repository, `dest` must have another
+    /// path when copying within the same repository.
+    pub fn copy_to(&self,
+                   dest: &Content,
+                   publish: bool,
+                   client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let url = self.get_dl_url(client);
+
+        let resp = client.get(url)
+            .header(AcceptEncoding(vec![qitem(Encoding::Identity)]))
+            .send()?;
+
+        let body = String::from("(body not logged)");
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("CopyContent({}): Copying to {}", self, dest);
+                let no_values: &[&str] = &[];
+                dest.upload_from_response(
+                    resp, publish, false, false, None,
+                    no_values, no_values, no_values, client)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "CopyContent",
+                    io::ErrorKind::NotFound,
+                    "File not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "CopyContent", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "CopyContent",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    fn upload_body<'a>(&self,
+                       body: Body<'a>,
+                       options: &UploadOptions,
+                       client: &'a BintrayClient)
+        -> Result<UploadOutcome, BintrayError>
+    {
+        header! { (XChecksumSha1, "X-Checksum-Sha1") => [String] }
+        header! { (XChecksumSha2, "X-Checksum-Sha2") => [String] }
+        header! { (XBintrayVersionAttributes, "X-Bintray-Version-Attributes") => [String] }
+
+        if self.checksum_header_algorithm.is_some() {
+            if let Some(checksum) = self.missing_checksum() {
+                error!("UploadContent({}): {} checksum unknown",
+                       self, checksum);
+                let error = io::Error::new(
+                    io::ErrorKind::InvalidInput, format!(
+                        "Bintray::UploadContent({}): {} checksum unknown",
+                        self, checksum));
+                return Err(BintrayError::from(error));
+            }
+        }
+
+        let repository_type = self.repository_type.clone()
+            .unwrap_or(RepositoryType::Generic);
+        let mut segments = repository_type.content_upload_segments(
+            &self.owner, &self.repository, &self.package, &self.version,
+            &self.path);
+
+        if repository_type == RepositoryType::Maven {
+            // Maven upload options are matrix parameters of the file
+            // name.
+            if let Some(file_name) = segments.last_mut() {
+                if options.publish {
+                    file_name.push_str(";publish=1");
+                }
+                if options.override_ || self.override_if_different {
+                    file_name.push_str(";override=1");
+                }
+            }
+        }
+
+        let mut url = client.get_base_url();
+        url.path_segments_mut().unwrap().extend(&segments);
+        if repository_type != RepositoryType::Maven {
+            if options.publish {
+                url.query_pairs_mut().append_pair("publish", "1");
+            }
+            if options.override_ || self.override_if_different {
+                url.query_pairs_mut().append_pair("override", "1");
+            }
+            if options.explode {
+                url.query_pairs_mut().append_pair("explode", "1");
+            }
+        }
+
+        let mut headers = Headers::new();
+        if let Some(algorithm) = self.get_checksum_header_algorithm() {
+            if algorithm != ChecksumAlgorithm::Sha256 {
+                if let Some(ref sha1) = self.sha1 {
+                    headers.set(XChecksumSha1(sha1.clone()));
+                }
+            }
+            if algorithm != ChecksumAlgorithm::Sha1 {
+                if let Some(ref sha256) = self.sha256 {
+                    headers.set(XChecksumSha2(sha256.clone()));
+                }
+            }
+        }
+
+        if !self.version_attributes.is_empty() &&
+            !self.version_exists(client)? {
+            let attributes = self.version_attributes_json();
+            headers.set(XBintrayVersionAttributes(attributes.to_string()));
+        }
+
+        let mut resp = client.put(url)
+            .headers(headers)
+            .add_gpg_passphrase(options.gpg_passphrase.as_deref())
+            .add_debian_architecture(&options.debian_architecture)
+            .add_debian_distribution(&options.debian_distribution)
+            .add_debian_component(&options.debian_component)
+            .body(body)
+            .send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Created => {
+                info!("UploadContent({}): {}", self, body);
+
+                report_bintray_warning!(
+                    self, resp, body, "UploadContent")
+                    .map(UploadOutcome::Uploaded)
+            }
+            _ if resp.status == StatusCode::Conflict &&
+                self.override_if_different &&
+                self.is_unchanged_conflict(&resp, &body) => {
+                info!("UploadContent({}): Remote file is identical: {}",
+                      self, body);
+                Ok(UploadOutcome::Unchanged)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "UploadContent", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
+                report_bintray_error!(
+                    self, resp, body, "UploadContent",
+                    io::ErrorKind::PermissionDenied,
+                    "Requires admin privileges")
+            }
+            _ if resp.status == StatusCode::Conflict => {
+                error!("UploadContent({}): {}\n{}",
+                       self, format_status_line!(resp),
+                       utils::prettify_json(&body));
+                Err(BintrayError::Conflict {
+                    object: self.to_string(),
+                    message: utils::get_bintray_error_message(
+                        body, resp.status, "Conflict with existing file"),
+                })
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "UploadContent",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Tells if the version of this file exists, i.e. if an upload
+    /// wouldn't create it.
+    fn version_exists(&self, client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        let mut version = Version::new(
+            &self.owner, &self.repository, &self.package, &self.version);
+        version.exists(client)
+    }
+
+    /// Returns `version_attributes` in the format of Bintray's
+    /// attributes API, the values of each name grouped together.
+    fn version_attributes_json(&self) -> Value {
+        let mut names: Vec<&str> = vec![];
+        for (name, _) in &self.version_attributes {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+
+        let attributes = names.into_iter()
+            .map(|name| {
+                let values = self.version_attributes.iter()
+                    .filter(|(n, _)| n == name)
+                    .map(|(_, value)| Value::String(value.clone()))
+                    .collect();
+
+                let mut attribute = Map::new();
+                attribute.insert(String::from("name"),
+                                 Value::String(String::from(name)));
+                attribute.insert(String::from("values"),
+                                 Value::Array(values));
+                attribute.insert(String::from("type"),
+                                 Value::String(String::from("string")));
+                Value::Object(attribute)
+            })
+            .collect();
+        Value::Array(attributes)
+    }
+
+    fn get_checksum_header_algorithm(&self) -> Option<ChecksumAlgorithm> {
+        match self.checksum_header_algorithm {
+            None if self.override_if_different => {
+                Some(ChecksumAlgorithm::Sha256)
+            }
+            algorithm => algorithm,
+        }
+    }
+
+    /// Returns the name of a checksum to send along with uploads but
+    /// which is unknown.
+    /// Tells if a 409 Conflict answer to an upload means the remote
+    /// file is identical to the uploaded one, as opposed to a real
+    /// conflict (e.g. a published file which can't be overridden).
+    ///
+    /// This is confirmed either by the remote SHA-256 checksum, if the
+    /// server reports it, or by the error message.
+    fn is_unchanged_conflict(&self, resp: &Response, body: &str) -> bool {
+        if let (Some(remote), Some(local)) =
+            (checksum_from_response(resp), &self.sha256) {
+            return remote == local.to_lowercase();
+        }
+
+        let message = utils::get_bintray_error_message(
+            String::from(body), resp.status, "").to_lowercase();
+        message.contains("unchanged") || message.contains("identical")
+    }
+
+    fn missing_checksum(&self) -> Option<&'static str> {
+        match self.get_checksum_header_algorithm() {
+            Some(ChecksumAlgorithm::Sha256) | Some(ChecksumAlgorithm::Both)
+                if self.sha256.is_none() => Some("SHA-256"),
+            Some(ChecksumAlgorithm::Sha1) | Some(ChecksumAlgorithm::Both)
+                if self.sha1.is_none() => Some("SHA-1"),
+            _ => None,
+        }
+    }
+
+    /// Uploads `local_filename` only if its SHA-256 checksum differs
+    /// from the remote file's one.
+    ///
+    /// The checksums are compared locally, unless
+    /// `set_override_if_different()` was used, in which case the server
+    /// does the comparison and no extra request is needed.
+    ///
+    /// The local checksum is stored in `sha1` and `sha256`. The remote
+    /// file is overridden if it exists. Returns true if the file was
+    /// uploaded, false if the remote file was already identical.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload_if_changed<T: Borrow<str>>(&mut self,
+                                            local_filename: &PathBuf,
+                                            publish: bool,
+                                            gpg_passphrase: Option<&str>,
+                                            debian_architecture: &[T],
+                                            debian_distribution: &[T],
+                                            debian_component: &[T],
+                                            client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        self.set_checksum_from_file(local_filename)?;
+
+        if !self.override_if_different {
+            let remote_checksum = self.get_remote_checksum(client)?;
+            if remote_checksum.is_some() && remote_checksum == self.sha256 {
+                info!("UploadContent({}): Remote file is identical, skipping",
+                      self);
+                return Ok(false);
+            }
+        }
+
+        let options = positional_options(
+            publish, true, false, gpg_passphrase,
+            debian_architecture, debian_distribution, debian_component);
+        let outcome = self.upload_file(local_filename, &options, None, client)?;
+
+        match outcome {
+            UploadOutcome::Uploaded(_) => Ok(true),
+            UploadOutcome::Unchanged   => Ok(false),
+        }
+    }
+
+    /// Returns true if the file can be downloaded, false otherwise.
+    ///
+    /// Only published files are visible.
+    pub fn exists(&self, client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        Ok(self.head(client)?.is_some())
+    }
+
+    /// Tells if the file can be downloaded and if it matches the local
+    /// SHA-256 checksum.
+    ///
+    /// Only a HEAD request is issued, like `exists()`.
+    pub fn status(&self, client: &BintrayClient)
+        -> Result<FileStatus, BintrayError>
+    {
+        match self.head(client)? {
+            Some(resp) => {
+                let remote_checksum = checksum_from_response(&resp);
+                let matches_checksum = match (&self.sha256, remote_checksum) {
+                    (Some(local), Some(remote)) => {
+                        local.to_lowercase() == remote.to_lowercase()
+                    }
+                    _ => false,
+                };
+                Ok(FileStatus::Present { matches_checksum })
+            }
+            None => Ok(FileStatus::Absent),
+        }
+    }
+
+    /// Returns true if the file is published, false if it was uploaded
+    /// but not published yet.
+    ///
+    /// Unlike `exists()`, this doesn't depend on the file being
+    /// available for download yet: the files of the version are listed
+    /// instead. An `io::ErrorKind::NotFound` error is returned if the
+    /// file is not part of the version.
+    pub fn is_published(&self, client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        if self.find_in_version(false, client)?.is_some() {
+            return Ok(true);
+        }
+        if self.find_in_version(true, client)?.is_some() {
+            return Ok(false);
+        }
+
+        self.not_found_in_version("IsPublished")
+    }
+
+    /// Returns the date and time at which the file was uploaded, as
+    /// reported by Bintray, published or not.
+    ///
+    /// An `io::ErrorKind::NotFound` error is returned if the file is not
+    /// part of the version.
+    pub fn uploaded_at(&self, client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        match self.find_in_version(true, client)? {
+            Some(file) => Ok(file.created),
+            None       => self.not_found_in_version("UploadedAt"),
+        }
+    }
+
+    /// Returns this file as listed in the files of its version.
+    fn find_in_version(&self,
+                       include_unpublished: bool,
+                       client: &BintrayClient)
+        -> Result<Option<Content>, BintrayError>
+    {
+        let version = Version::new(
+            &self.owner, &self.repository, &self.package, &self.version);
+        let path = clean_path(&self.path);
+
+        Ok(version.list_files(include_unpublished, client)?
+           .into_iter()
+           .find(|file| file.path == path))
+    }
+
+    fn not_found_in_version<T>(&self, function: &str)
+        -> Result<T, BintrayError>
+    {
+        error!("{}({}): File not found in version", function, self);
+        let error = io::Error::new(
+            io::ErrorKind::NotFound, format!(
+                "Bintray::{}({}): File not found in version",
+                function, self));
+        Err(BintrayError::from(error))
+    }
+
+    /// Waits until the file can be downloaded.
+    ///
+    /// An `io::ErrorKind::TimedOut` error is returned if it is still
+    /// not available after `timeout`.
+    pub fn wait_for_availability(&self,
+                                 timeout: Duration,
+                                 client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        utils::wait_for_condition(self, "WaitForAvailability", timeout, || {
+            self.exists(client)
+        })
+    }
+
+    /// Returns the type of the repository holding this file.
+    ///
+    /// Unless it is already known (see `with_repository()`), it is
+    /// resolved on first use and cached by the client, so creating many
+    /// `Content` structures for the same repository stays cheap.
+    pub fn get_repository_type(&self, client: &BintrayClient)
+        -> Result<RepositoryType, BintrayError>
+    {
+        match self.repository_type {
+            Some(ref type_) => Ok(type_.clone()),
+            None => client.get_repository_type(&self.owner, &self.repository),
+        }
+    }
+
+    /// Returns the other files of the version this file belongs to.
+    ///
+    /// The returned list is empty if this file is alone in its version.
+    pub fn siblings(&self,
+                    include_unpublished: bool,
+                    client: &BintrayClient)
+        -> Result<Vec<Content>, BintrayError>
+    {
+        let version = Version::new(&self.owner,
+                                   &self.repository,
+                                   &self.package,
+                                   &self.version);
+        let files = version.list_files(include_unpublished, client)?;
+
+        Ok(files.into_iter()
+           .filter(|file| file.path != self.path)
+           .collect())
+    }
+
+    /// Returns the entitlements of the repository whose path covers this
+    /// file, from the least to the most privileged.
+    pub fn entitlements(&self, client: &BintrayClient)
+        -> Result<Vec<Entitlement>, BintrayError>
+    {
+        let repository = Repository::new(&self.owner, &self.repository);
+
+        let mut entitlements: Vec<Entitlement> = repository
+            .list_entitlements(client)?
+            .into_iter()
+            .filter(|entitlement| entitlement.covers(&self.path))
+            .collect();
+        entitlements.sort_by(|a, b| {
+            a.access.cmp(&b.access).then_with(|| a.id.cmp(&b.id))
+        });
+
+        Ok(entitlements)
+    }
+
+    /// Returns the SHA-256 checksum reported by Bintray for the remote
+    /// file, or `None` if the file doesn't exist.
+    pub fn get_remote_checksum(&self, client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        client.content_checksum(&self.owner, &self.repository, &self.path)
+    }
+
+    fn head(&self, client: &BintrayClient)
+        -> Result<Option<Response>, BintrayError>
+    {
+        let url = self.get_dl_url(client);
+
+        let resp = client.head(url)
+            .send()?;
+
+        let body = String::new();
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("HeadContent({}): Ok", self);
+                Ok(Some(resp))
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                info!("HeadContent({}): Not found", self);
+                Ok(None)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "HeadContent", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "HeadContent",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    fn get_dl_url(&self, client: &BintrayClient) -> Url {
+        let mut url = client.get_dl_base_url();
+        if let Some(ref host) = self.dl_host {
+            let other_url = Url::parse(
+                &format!("{}://{}{}", url.scheme(), host, url.path()));
+            match other_url {
+                Ok(other_url) => url = other_url,
+                Err(_) => {
+                    warn!("GetDownloadUrl({}): Ignoring invalid host \"{}\"",
+                          self, host);
+                }
+            }
+        }
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.extend(&[&self.owner,
+                        &self.repository]);
+            path.extend(self.path.iter().map(|v| v.to_string_lossy()));
+        }
+        url
+    }
+
+    /// Returns the URLs of the repository metadata files indexing this
+    /// file.
+    ///
+    /// For an RPM repository, this is the `repomd.xml` file, taking the
+    /// repository `yum_metadata_depth` into account. For a Debian
+    /// repository, this is the `Release` file of each distribution and
+    /// the `Packages` file of each distribution/component/architecture
+    /// combination; when a list is empty, the repository default is
+    /// used. Other repository types have no index and an empty list is
+    /// returned.
+    ///
+    /// The repository is queried to get its type and settings, unless
+    /// they are already known (see `Content::with_repository()`).
+    pub fn index_urls<T: Borrow<str>>(&self,
+                                      debian_distribution: &[T],
+                                      debian_component: &[T],
+                                      debian_architecture: &[T],
+                                      client: &BintrayClient)
+        -> Result<Vec<Url>, BintrayError>
+    {
+        let mut repository = Repository::new(&self.owner, &self.repository);
+        let needs_debian_defaults =
+            debian_distribution.is_empty() ||
+            debian_component.is_empty() ||
+            debian_architecture.is_empty();
+        match self.repository_type {
+            Some(RepositoryType::Rpm) => {
+                repository.type_ = RepositoryType::Rpm;
+                repository.yum_metadata_depth = self.yum_metadata_depth;
+                repository.yum_groups_file = self.yum_groups_file.clone();
+            }
+            Some(RepositoryType::Debian) if !needs_debian_defaults => {
+                repository.type_ = RepositoryType::Debian;
+            }
+            Some(ref type_) if *type_ != RepositoryType::Debian => {
+                repository.type_ = type_.clone();
+            }
+            _ => {
+                repository =
+                    client.get_repository(&self.owner, &self.repository)?;
+            }
+        }
+
+        let mut base_url = client.get_dl_base_url();
+        {
+            let mut path = base_url.path_segments_mut().unwrap();
+            path.extend(&[&self.owner,
+                        &self.repository]);
+        }
+
+        let mut urls = vec![];
+        match repository.type_ {
+            RepositoryType::Rpm => {
+                let depth = repository.yum_metadata_depth.unwrap_or(0);
+
+                let mut url = base_url.clone();
+                {
+                    let mut path = url.path_segments_mut().unwrap();
+                    if let Some(parent) = self.path.parent() {
+                        path.extend(parent.iter()
+                                    .take(depth as usize)
+                                    .map(|v| v.to_string_lossy()));
+                    }
+                    path.extend(&["repodata", "repomd.xml"]);
+                }
+                urls.push(url);
+            }
+            RepositoryType::Debian | RepositoryType::Deb => {
+                let distributions = or_default(
+                    debian_distribution,
+                    &repository.default_debian_distribution);
+                let components = or_default(
+                    debian_component,
+                    &repository.default_debian_component);
+                let architectures = or_default(
+                    debian_architecture,
+                    &repository.default_debian_architecture);
+
+                for distribution in &distributions {
+                    let mut url = base_url.clone();
+                    url.path_segments_mut().unwrap()
+                        .extend(&["dists", distribution, "Release"]);
+                    urls.push(url);
+
+                    for component in &components {
+                        for architecture in &architectures {
+                            let binary = format!("binary-{}", architecture);
+                            let mut url = base_url.clone();
+                            url.path_segments_mut().unwrap()
+                                .extend(&["dists",
+                                        distribution,
+                                        component,
+                                        &binary,
+                                        "Packages"]);
+                            urls.push(url);
+                        }
+                    }
+                }
+            }
+            _ => { }
+        }
+
+        Ok(urls)
+    }
+
+    /// Returns the URL the file is actually downloaded from, without
+    /// following redirections.
+    ///
+    /// If Bintray redirects the download (e.g. to a CDN), the target of
+    /// the redirection is returned. Otherwise, the download URL itself
+    /// is returned. This allows to know which host must be reachable to
+    /// download the file.
+    pub fn get_download_location(&self, client: &BintrayClient)
+        -> Result<Url, BintrayError>
+    {
+        let url = self.get_dl_url(client);
+
+        let resp = client.request_without_redirect(Method::Head, url.clone())
+            .send()?;
+
+        let body = String::new();
+
+        match resp {
+            _ if resp.status.is_redirection() => {
+                let location = match resp.headers.get::<Location>() {
+                    Some(location) => url.join(location),
+                    None => {
+                        return report_bintray_error!(
+                            self, resp, body, "GetDownloadLocation",
+                            io::ErrorKind::InvalidData,
+                            "Redirection without a location");
+                    }
+                };
+                let location = location.map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, e)
+                })?;
+
+                info!("GetDownloadLocation({}): Redirected to {}",
+                      self, location);
+                Ok(location)
+            }
+            _ if resp.status == StatusCode::Ok => {
+                info!("GetDownloadLocation({}): Not redirected", self);
+                Ok(url)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "GetDownloadLocation",
+                    io::ErrorKind::NotFound,
+                    "File not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetDownloadLocation", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "GetDownloadLocation",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Downloads the file to `local_filename`.
+    ///
+    /// The file is first written to a temporary file in the same
+    /// directory, then renamed to `local_filename` once complete. On
+    /// error, the temporary file is removed, so a partial download is
+    /// never left behind.
+    ///
+    /// The data are requested with `Accept-Encoding: identity`, so the
+    /// file is written exactly as stored by Bintray, even for
+    /// compressed artifacts.
+    pub fn download(&self,
+                    local_filename: &PathBuf,
+                    client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        self.download_and_verify(local_filename, false, client)
+    }
 
-            created: None,
-            size: None,
-            sha1: None,
-        }
+    /// Same as `download()`, but the downloaded data are also verified
+    /// against the `sha1` checksum, if it is known. On mismatch,
+    /// `local_filename` is left untouched.
+    pub fn download_verified(&self,
+                             local_filename: &PathBuf,
+                             client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        self.download_and_verify(local_filename, true, client)
     }
 
-    pub fn upload<T: Borrow<str>>(&self,
-                                 local_filename: &PathBuf,
-                                 publish: bool,
-                                 override_: bool,
-                                 explode: bool,
-                                 gpg_passphrase: Option<&str>,
-                                 debian_architecture: &[T],
-                                 debian_distribution: &[T],
-                                 debian_component: &[T],
-                                 client: &BintrayClient)
-        -> Result<Option<String>, BintrayError>
+    /// Downloads the file into `cache_dir` and returns its local path.
+    ///
+    /// The file is stored under `<cache_dir>/<owner>/<repository>/<path>`,
+    /// along with the `ETag` returned by the server. On subsequent
+    /// calls, the `ETag` is sent in an `If-None-Match` header and the
+    /// cached file is reused if the server replies `304 Not Modified`.
+    ///
+    /// If the owner, the repository or the path could point outside of
+    /// `cache_dir`, `BintrayError::UnsafePath` is returned.
+    pub fn download_cached(&self,
+                           cache_dir: &Path,
+                           client: &BintrayClient)
+        -> Result<PathBuf, BintrayError>
     {
-        // TODO: Suport Maven upload which use a different URL.
+        let local_filename = join_safe_path(cache_dir, &self.owner)
+            .and_then(|dir| join_safe_path(&dir, &self.repository))
+            .and_then(|dir| join_safe_path(&dir, clean_path(&self.path)))
+            .map_err(|e| {
+                error!("DownloadContentCached({}): Unsafe path", self);
+                e
+            })?;
+        let file_name = local_filename.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let etag_filename = local_filename.with_file_name(
+            format!(".{}.etag", file_name));
 
-        let mut url = client.get_base_url();
-        {
-            let mut path = url.path_segments_mut().unwrap();
-            path.extend(&["content",
-                        &self.owner,
-                        &self.repository,
-                        &self.package,
-                        &self.version]);
-            path.extend(self.path.iter().map(|v| v.to_string_lossy()));
-        }
+        let cached_etag = if local_filename.is_file() {
+            fs::read_to_string(&etag_filename).ok()
+                .map(|etag| String::from(etag.trim()))
+                .filter(|etag| !etag.is_empty())
+        } else {
+            None
+        };
 
-        if publish {
-            url.query_pairs_mut().append_pair("publish", "1");
-        }
-        if override_ {
-            url.query_pairs_mut().append_pair("override", "1");
-        }
-        if explode {
-            url.query_pairs_mut().append_pair("explode", "1");
-        }
+        let url = self.get_dl_url(client);
 
-        let local_file = File::open(local_filename)?;
-        let local_file_size = local_file.metadata()?.len();
-        let mut local_file_reader = BufReader::new(local_file);
+        let mut headers = Headers::new();
+        if let Some(ref etag) = cached_etag {
+            headers.set_raw("If-None-Match", vec![etag.clone().into_bytes()]);
+        }
 
-        let mut resp = client.put(url)
-            .add_gpg_passphrase(gpg_passphrase)
-            .add_debian_architecture(debian_architecture)
-            .add_debian_distribution(debian_distribution)
-            .add_debian_component(debian_component)
-            .body(Body::SizedBody(&mut local_file_reader, local_file_size))
+        let mut resp = client.get(url)
+            .header(AcceptEncoding(vec![qitem(Encoding::Identity)]))
+            .headers(headers)
             .send()?;
 
-        let mut body = String::new();
-        resp.read_to_string(&mut body)?;
+        let body = String::from("(body not logged)");
 
         match resp {
-            _ if resp.status == StatusCode::Created => {
-                info!("UploadContent({}): {}", self, body);
+            _ if resp.status == StatusCode::NotModified &&
+                cached_etag.is_some() => {
+                info!("DownloadContentCached({}): Not modified, using {}",
+                      self, local_filename.display());
+                Ok(local_filename)
+            }
+            _ if resp.status == StatusCode::Ok => {
+                info!("DownloadContentCached({}): Ok {}", self, body);
 
-                report_bintray_warning!(
-                    self, resp, body, "UploadContent")
+                if let Some(parent) = local_filename.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let tmp_filename = temp_filename(&local_filename);
+
+                if let Err(e) = self.write_download(
+                    &mut resp, &tmp_filename, false) {
+                    let _ = fs::remove_file(&tmp_filename);
+                    return Err(e);
+                }
+
+                // The ETag is removed before the file is replaced, so a
+                // stale ETag never matches a newer file.
+                let _ = fs::remove_file(&etag_filename);
+                fs::rename(&tmp_filename, &local_filename)?;
+                if let Some(etag) = utils::get_raw_header(
+                    &resp.headers, "ETag") {
+                    fs::write(&etag_filename, etag)?;
+                }
+                Ok(local_filename)
             }
-            _ if resp.status == StatusCode::Unauthorized => {
+            _ if resp.status == StatusCode::NotFound => {
                 report_bintray_error!(
-                    self, resp, body, "UploadContent",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                    self, resp, body, "DownloadContentCached",
+                    io::ErrorKind::NotFound,
+                    "File not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "DownloadContentCached", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
-                    self, resp, body, "UploadContent",
+                    self, resp, body, "DownloadContentCached",
                     io::ErrorKind::PermissionDenied,
                     "Requires admin privileges")
             }
-            _ if resp.status == StatusCode::Conflict => {
-                report_bintray_error!(
-                    self, resp, body, "UploadContent",
-                    io::ErrorKind::PermissionDenied,
-                    "Conflict with existing file")
-            }
             _ => {
                 report_bintray_error!(
-                    self, resp, body, "UploadContent",
+                    self, resp, body, "DownloadContentCached",
                     io::ErrorKind::Other,
                     "Unrecognized error")
             }
         }
     }
 
-    pub fn download(&self,
-                    local_filename: &PathBuf,
-                    client: &BintrayClient)
+    fn download_and_verify(&self,
+                           local_filename: &PathBuf,
+                           verify: bool,
+                           client: &BintrayClient)
         -> Result<(), BintrayError>
     {
-        let mut url = client.get_dl_base_url();
-        {
-            let mut path = url.path_segments_mut().unwrap();
-            path.extend(&[&self.owner,
-                        &self.repository]);
-            path.extend(self.path.iter().map(|v| v.to_string_lossy()));
-        }
+        let url = self.get_dl_url(client);
 
         let mut resp = client.get(url)
+            .header(AcceptEncoding(vec![qitem(Encoding::Identity)]))
             .send()?;
 
         let body = String::from("(body not logged)");
@@ -156,23 +1587,18 @@ impl Content {
             _ if resp.status == StatusCode::Ok => {
                 info!("DownloadContent({}): Ok {}", self, body);
 
-                let mut local_file = OpenOptions::new()
-                    .write(true)
-                    .create(true)
-                    .truncate(true)
-                    .open(local_filename)?;
-
-                let buffer = &mut vec![0; 65536];
-                loop {
-                    let len_read = resp.read(buffer)?;
-                    if len_read == 0 {
-                        break;
-                    }
+                let tmp_filename = temp_filename(local_filename);
 
-                    local_file.write_all(&buffer[..len_read])?;
+                match self.write_download(&mut resp, &tmp_filename, verify) {
+                    Ok(()) => {
+                        fs::rename(&tmp_filename, local_filename)?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        let _ = fs::remove_file(&tmp_filename);
+                        Err(e)
+                    }
                 }
-
-                Ok(())
             }
             _ if resp.status == StatusCode::NotFound => {
                 report_bintray_error!(
@@ -181,10 +1607,8 @@ impl Content {
                     "File not found")
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "DownloadContent",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "DownloadContent", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -192,6 +1616,19 @@ impl Content {
                     io::ErrorKind::PermissionDenied,
                     "Requires admin privileges")
             }
+            _ if resp.status.is_server_error() => {
+                let mut body = String::new();
+                resp.read_to_string(&mut body)?;
+                error!("DownloadContent({}): {}\n{}",
+                       self, format_status_line!(resp),
+                       utils::prettify_json(&body));
+                Err(BintrayError::ServerError {
+                    object: self.to_string(),
+                    status: resp.status.to_u16(),
+                    message: utils::get_bintray_error_message(
+                        body, resp.status, "Server error"),
+                })
+            }
             _ => {
                 report_bintray_error!(
                     self, resp, body, "DownloadContent",
@@ -201,8 +1638,342 @@ impl Content {
         }
     }
 
+    /// Downloads `len` bytes of the file, starting at offset `start`.
+    ///
+    /// This allows to sniff the type of a file or to read an archive
+    /// header without downloading the whole file. Fewer bytes are
+    /// returned if the file is shorter. An error is returned if the
+    /// server ignores the requested range. A range ending past the
+    /// largest offset, `u64::MAX`, is refused with an
+    /// `io::ErrorKind::InvalidInput` error before any request is sent.
+    pub fn download_range(&self,
+                          start: u64,
+                          len: u64,
+                          client: &BintrayClient)
+        -> Result<Vec<u8>, BintrayError>
+    {
+        if len == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut resp = self.request_range(
+            "DownloadContentRange", start, len, client)?;
+
+        let body = String::from("(body not logged)");
+
+        match resp {
+            _ if resp.status == StatusCode::PartialContent => {
+                info!("DownloadContentRange({}): Ok {}", self, body);
+
+                let mut data = vec![];
+                (&mut resp).take(len).read_to_end(&mut data)?;
+                Ok(data)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "DownloadContentRange",
+                    io::ErrorKind::InvalidData,
+                    "Range ignored by the server")
+            }
+        }
+    }
+
+    /// Downloads the file to `local_filename` in `chunks` parts, using
+    /// Range requests, up to `concurrency` of them in parallel.
+    ///
+    /// This makes better use of the bandwidth of high-latency links for
+    /// large files. The size of the file is queried first; the parts
+    /// are then written at their offset in a temporary file, renamed to
+    /// `local_filename` once complete, like `download()`.
+    ///
+    /// The assembled file is verified against the `sha256` checksum,
+    /// or the one reported by Bintray if it is unknown. If the server
+    /// ignores Range requests, the file is downloaded in a single
+    /// stream instead.
+    pub fn download_parallel(&self,
+                             local_filename: &PathBuf,
+                             chunks: usize,
+                             concurrency: usize,
+                             client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        let (size, remote_checksum) = match self.head(client)? {
+            Some(resp) => {
+                let size = resp.headers.get::<ContentLength>()
+                    .map(|&ContentLength(size)| size);
+                (size, checksum_from_response(&resp))
+            }
+            None => {
+                error!("DownloadContentParallel({}): File not found", self);
+                let error = io::Error::new(
+                    io::ErrorKind::NotFound, format!(
+                        "Bintray::DownloadContentParallel({}): \
+                         File not found", self));
+                return Err(BintrayError::from(error));
+            }
+        };
+        let size = match size {
+            Some(size) if size > 0 => size,
+            _ => {
+                info!("DownloadContentParallel({}): Size unknown, \
+                       downloading in a single stream", self);
+                return self.download(local_filename, client);
+            }
+        };
+
+        let chunks = cmp::max(chunks, 1) as u64;
+        let chunk_len = size / chunks + if size % chunks > 0 { 1 } else { 0 };
+        let ranges: Vec<(u64, u64)> = (0..chunks)
+            .map_while(|index| index.checked_mul(chunk_len))
+            .take_while(|&start| start < size)
+            .map(|start| (start, cmp::min(chunk_len, size - start)))
+            .filter(|&(_, len)| len > 0)
+            .collect();
+
+        let tmp_filename = temp_filename(local_filename);
+
+        let expected = self.sha256.clone().or(remote_checksum);
+        let result = self.write_parallel_download(
+            &tmp_filename, size, &ranges, concurrency, client)
+            .and_then(|()| self.verify_sha256(&tmp_filename, expected));
+        match result {
+            Ok(()) => {
+                fs::rename(&tmp_filename, local_filename)?;
+                info!("DownloadContentParallel({}): Ok ({} bytes, {} \
+                       part(s))", self, size, ranges.len());
+                Ok(())
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_filename);
+                Err(e)
+            }
+        }
+    }
+
+    fn write_parallel_download(&self,
+                               tmp_filename: &PathBuf,
+                               size: u64,
+                               ranges: &[(u64, u64)],
+                               concurrency: usize,
+                               client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        let local_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(tmp_filename)?;
+
+        // The first part tells if the server supports Range requests.
+        let (start, len) = ranges[0];
+        let mut resp = self.request_range(
+            "DownloadContentParallel", start, len, client)?;
+        if resp.status == StatusCode::Ok {
+            warn!("DownloadContentParallel({}): Range ignored by the \
+                   server, downloading in a single stream", self);
+            return self.write_download(&mut resp, tmp_filename, false);
+        }
+        local_file.set_len(size)?;
+        self.write_range(&mut resp, tmp_filename, start, len)?;
+
+        utils::parallel_map(&ranges[1..], concurrency, |&(start, len)| {
+            self.request_range("DownloadContentParallel", start, len, client)
+                .and_then(|mut resp| {
+                    self.write_range(&mut resp, tmp_filename, start, len)
+                })
+        })
+            .into_iter()
+            .collect()
+    }
+
+    /// Copies the body of a "206 Partial Content" response at offset
+    /// `start` of `local_filename`.
+    fn write_range(&self,
+                   resp: &mut Response,
+                   local_filename: &PathBuf,
+                   start: u64,
+                   len: u64)
+        -> Result<(), BintrayError>
+    {
+        if resp.status != StatusCode::PartialContent {
+            let body = String::from("(body not logged)");
+            return report_bintray_error!(
+                self, resp, body, "DownloadContentParallel",
+                io::ErrorKind::InvalidData,
+                "Range ignored by the server");
+        }
+
+        let mut local_file = OpenOptions::new()
+            .write(true)
+            .open(local_filename)?;
+        local_file.seek(SeekFrom::Start(start))?;
+        let copied = io::copy(&mut resp.take(len), &mut local_file)?;
+        if copied != len {
+            error!("DownloadContentParallel({}): Got {} bytes at offset {}, \
+                    expected {}", self, copied, start, len);
+            let error = io::Error::new(
+                io::ErrorKind::UnexpectedEof, format!(
+                    "Bintray::DownloadContentParallel({}): Truncated part \
+                     at offset {}", self, start));
+            return Err(BintrayError::from(error));
+        }
+        Ok(())
+    }
+
+    /// Compares the SHA-256 checksum of `local_filename` to `expected`.
+    /// Nothing is checked if `expected` is `None`.
+    fn verify_sha256(&self,
+                     local_filename: &PathBuf,
+                     expected: Option<String>)
+        -> Result<(), BintrayError>
+    {
+        let expected = match expected {
+            Some(expected) => expected.to_lowercase(),
+            None => {
+                warn!("DownloadContentParallel({}): SHA-256 checksum \
+                       unknown, not verified", self);
+                return Ok(());
+            }
+        };
+
+        let mut local = self.clone();
+        local.set_checksum_from_file(local_filename)?;
+        match local.sha256 {
+            Some(ref sha256) if *sha256 == expected => Ok(()),
+            sha256 => {
+                error!("DownloadContentParallel({}): Checksum mismatch: \
+                        expected {}, got {}",
+                       self, expected, sha256.unwrap_or_default());
+                let error = io::Error::new(
+                    io::ErrorKind::InvalidData, format!(
+                        "Bintray::DownloadContentParallel({}): \
+                         Checksum mismatch", self));
+                Err(BintrayError::from(error))
+            }
+        }
+    }
+
+    /// Requests `len` bytes of the file, starting at offset `start`.
+    ///
+    /// The response is returned if the status is "206 Partial Content",
+    /// or "200 OK" if the server ignored the range.
+    fn request_range(&self,
+                     function: &str,
+                     start: u64,
+                     len: u64,
+                     client: &BintrayClient)
+        -> Result<Response, BintrayError>
+    {
+        // The last byte is start + len - 1, computed so that a range
+        // ending at u64::MAX is still valid.
+        let end = match len.checked_sub(1)
+            .and_then(|last| start.checked_add(last)) {
+            Some(end) => end,
+            _ => {
+                error!("{}({}): Invalid range of {} bytes at offset {}",
+                       function, self, len, start);
+                let error = io::Error::new(
+                    io::ErrorKind::InvalidInput, format!(
+                        "Bintray::{}({}): Invalid range of {} bytes at \
+                         offset {}", function, self, len, start));
+                return Err(BintrayError::from(error));
+            }
+        };
+
+        let url = self.get_dl_url(client);
+
+        let resp = client.get(url)
+            .header(AcceptEncoding(vec![qitem(Encoding::Identity)]))
+            .header(Range::Bytes(vec![ByteRangeSpec::FromTo(start, end)]))
+            .send()?;
+
+        let body = String::from("(body not logged)");
+
+        match resp {
+            _ if resp.status == StatusCode::PartialContent ||
+                resp.status == StatusCode::Ok => {
+                Ok(resp)
+            }
+            _ if resp.status == StatusCode::RangeNotSatisfiable => {
+                report_bintray_error!(
+                    self, resp, body, function,
+                    io::ErrorKind::InvalidInput,
+                    "Range beyond the end of the file")
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, function,
+                    io::ErrorKind::NotFound,
+                    "File not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, function, client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, function,
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    fn write_download(&self,
+                      resp: &mut Response,
+                      local_filename: &PathBuf,
+                      verify: bool)
+        -> Result<(), BintrayError>
+    {
+        let mut local_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(local_filename)?;
+
+        let mut sha1 = Sha1::default();
+        let buffer = &mut vec![0; 65536];
+        loop {
+            let len_read = resp.read(buffer)?;
+            if len_read == 0 {
+                break;
+            }
+
+            local_file.write_all(&buffer[..len_read])?;
+            sha1.input(&buffer[..len_read]);
+        }
+
+        let sha1 = format!("{:x}", sha1.result());
+        match self.sha1 {
+            Some(ref expected) if verify && expected.to_lowercase() != sha1 => {
+                error!("DownloadContent({}): Checksum mismatch: \
+                        expected {}, got {}", self, expected, sha1);
+                let error = io::Error::new(
+                    io::ErrorKind::InvalidData, format!(
+                        "Bintray::DownloadContent({}): Checksum mismatch",
+                        self));
+                Err(BintrayError::from(error))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn show_in_download_list(&self, show: bool, client: &BintrayClient)
         -> Result<Option<String>, BintrayError>
+    {
+        self.set_file_metadata("list_in_downloads", Value::Bool(show), client)
+    }
+
+    /// Sets a metadata of the file.
+    ///
+    /// Bintray currently accepts the following keys:
+    /// * `list_in_downloads` (boolean): whether the file appears in the
+    ///   download list of the repository (see `show_in_download_list()`).
+    pub fn set_file_metadata(&self,
+                             key: &str,
+                             value: Value,
+                             client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
     {
         let mut url = client.get_base_url();
         {
@@ -213,18 +1984,12 @@ impl Content {
             path.extend(self.path.iter().map(|v| v.to_string_lossy()));
         }
 
-        #[derive(Serialize)]
-        struct ShowInDownloadListReq {
-            list_in_downloads: bool
-        };
-
-        let args = ShowInDownloadListReq {
-            list_in_downloads: show,
-        };
+        let mut args = Map::new();
+        args.insert(String::from(key), value);
 
         let json = serde_json::to_string_pretty(&args)?;
         info!(
-            "ShowInDownloadList({}): Submitting the following properties:\n{}",
+            "SetFileMetadata({}): Submitting the following properties:\n{}",
             self, json);
 
         let mut resp = client.put(url)
@@ -236,33 +2001,29 @@ impl Content {
 
         match resp {
             _ if resp.status == StatusCode::Ok => {
-                info!("ShowInDownloadList({}): {}", self, body);
+                info!("SetFileMetadata({}): {}", self, body);
 
                 report_bintray_warning!(
-                    self, resp, body, "ShowInDownloadList")
+                    self, resp, body, "SetFileMetadata")
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "ShowInDownloadList",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "SetFileMetadata", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
-                report_bintray_error!(
-                    self, resp, body, "ShowInDownloadList",
-                    io::ErrorKind::PermissionDenied,
-                    "Requires admin privileges")
+                report_bintray_forbidden!(
+                    self, resp, body, "SetFileMetadata")
             }
             _ if resp.status == StatusCode::BadRequest &&
             body.contains("unpublished file") => {
                 report_bintray_error!(
-                    self, resp, body, "ShowInDownloadList",
+                    self, resp, body, "SetFileMetadata",
                     io::ErrorKind::NotFound,
                     "File is not yet published")
             }
             _ => {
                 report_bintray_error!(
-                    self, resp, body, "ShowInDownloadList",
+                    self, resp, body, "SetFileMetadata",
                     io::ErrorKind::Other,
                     "Unrecognized error")
             }
@@ -295,10 +2056,8 @@ impl Content {
                     self, resp, body, "DeleteContent")
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "DeleteContent",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "DeleteContent", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -316,6 +2075,222 @@ impl Content {
     }
 }
 
+/// A reader failing as soon as a cancellation flag is set.
+struct CancellableReader<'a, R: Read> {
+    inner: R,
+    cancel: Option<&'a AtomicBool>,
+}
+
+impl<'a, R: Read> Read for CancellableReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.cancel {
+            Some(cancel) if cancel.load(AtomicOrdering::SeqCst) => {
+                Err(io::Error::new(io::ErrorKind::ConnectionAborted,
+                                   "Upload cancelled"))
+            }
+            _ => self.inner.read(buf),
+        }
+    }
+}
+
+/// A reader failing if the data it reads are shorter or longer than
+/// the declared size, so a body matching the `Content-Length` sent is
+/// never silently truncated or padded.
+struct SizeCheckingReader<R: Read> {
+    inner: R,
+    declared: u64,
+    read: u64,
+    eof: bool,
+}
+
+impl<R: Read> SizeCheckingReader<R> {
+    fn size_changed(&self) -> bool {
+        self.read > self.declared || (self.eof && self.read < self.declared)
+    }
+}
+
+impl<R: Read> Read for SizeCheckingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.read += len as u64;
+        self.eof = len == 0;
+        if self.size_changed() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "File size changed during upload"));
+        }
+        Ok(len)
+    }
+}
+
+fn or_default<T: Borrow<str>>(values: &[T], default: &Option<String>)
+    -> Vec<String>
+{
+    if values.is_empty() {
+        default.iter().cloned().collect()
+    } else {
+        values.iter().map(|s| String::from(s.borrow())).collect()
+    }
+}
+
+/// Downloads many files under `dest_dir`, keeping their paths relative
+/// to the repository root.
+///
+/// Up to `concurrency` files are downloaded in parallel by a fixed pool
+/// of threads, each one with `Content::download_verified()`. A download
+/// failing with a transient error (see `BintrayError::is_transient()`)
+/// is retried up to `retries` times. A file whose path could point
+/// outside of `dest_dir` (absolute or containing `..`) is not downloaded
+/// and fails with `BintrayError::UnsafePath`. Failures don't stop the
+/// other downloads: they are collected in the returned report.
+///
+/// `progress` is called after each file, successful or not, with the
+/// number of files done so far and the total number of files.
+pub fn download_many<F>(files: &[Content],
+                        dest_dir: &Path,
+                        concurrency: usize,
+                        retries: usize,
+                        progress: F,
+                        client: &BintrayClient)
+    -> DownloadReport
+    where F: Fn(usize, usize) + Sync
+{
+    let done = AtomicUsize::new(0);
+
+    let results = utils::parallel_map(files, concurrency, |file| {
+        let result = join_safe_path(dest_dir, &file.path)
+            .map_err(|e| {
+                error!("DownloadMany({}): Unsafe path", file);
+                e
+            })
+            .and_then(|local_filename| {
+                download_with_retries(file, &local_filename, retries, client)
+                    .map(|()| local_filename)
+            });
+
+        progress(done.fetch_add(1, AtomicOrdering::SeqCst) + 1, files.len());
+        result
+    });
+
+    let mut report = DownloadReport::default();
+    for (file, result) in files.iter().zip(results) {
+        match result {
+            Ok(local_filename) => report.downloaded.push(local_filename),
+            Err(e)             => report.failed.push((file.clone(), e)),
+        }
+    }
+
+    info!("DownloadMany: {} file(s) downloaded to {}, {} failed",
+          report.downloaded.len(), dest_dir.display(), report.failed.len());
+    report
+}
+
+/// Downloads `file` to `local_filename` for `download_many()`, retrying
+/// up to `retries` times on transient errors.
+fn download_with_retries(file: &Content,
+                         local_filename: &PathBuf,
+                         retries: usize,
+                         client: &BintrayClient)
+    -> Result<(), BintrayError>
+{
+    let mut attempt = 0;
+    loop {
+        let result = local_filename.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(BintrayError::from)
+            .and_then(|()| file.download_verified(local_filename, client));
+        match result {
+            Err(ref e) if e.is_transient() && attempt < retries => {
+                attempt += 1;
+                warn!("DownloadMany({}): Attempt {} failed, retrying: {}",
+                      file, attempt, e);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Returns the sum of the sizes of `files`. Files with an unknown size
+/// are ignored.
+pub fn total_size(files: &[Content]) -> u64 {
+    files.iter()
+        .filter_map(|file| file.size)
+        .map(|size| size as u64)
+        .sum()
+}
+
+/// Returns true if each file has a per-file `.asc` signature next to
+/// it, as created by `Version::sign()`. Signatures themselves are not
+/// expected to be signed. An empty list is not signed.
+///
+/// This is only a heuristic: it doesn't look at the repository
+/// metadata, which is what package managers verify for Debian and RPM
+/// repositories (see `Repository::signing_report()`).
+pub fn all_files_have_signature(files: &[Content]) -> bool {
+    let paths: HashSet<&PathBuf> = files.iter()
+        .map(|file| &file.path)
+        .collect();
+    let mut signed_files = paths.iter()
+        .filter(|path| path.extension().map_or(true, |ext| ext != "asc"))
+        .map(|path| {
+            let mut signature = path.as_os_str().to_owned();
+            signature.push(".asc");
+            paths.contains(&PathBuf::from(signature))
+        })
+        .peekable();
+    signed_files.peek().is_some() && signed_files.all(|signed| signed)
+}
+
+/// Returns the SHA-256 checksum from the `X-Checksum-Sha2` header of a
+/// download response, if any.
+pub fn checksum_from_response(resp: &Response) -> Option<String> {
+    utils::get_raw_header(&resp.headers, "X-Checksum-Sha2")
+        .map(|value| value.to_lowercase())
+}
+
+/// Counter making the names of temporary download files unique within
+/// this process.
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the name of the temporary file a download to `local_filename`
+/// is written to before being renamed.
+///
+/// This is a hidden file in the same directory, made unique by the
+/// process ID and a counter, so concurrent downloads to the same file
+/// don't overwrite each other's data.
+fn temp_filename(local_filename: &Path) -> PathBuf {
+    let file_name = local_filename.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("download"));
+    let counter = TEMP_FILE_COUNTER.fetch_add(1, AtomicOrdering::SeqCst);
+    local_filename.with_file_name(
+        format!(".{}.{}.{}.part", file_name, process::id(), counter))
+}
+
+/// Returns true if `path` is absolute or contains `..`, i.e. if it
+/// could point outside of a directory it is joined to.
+fn is_unsafe_path(path: &Path) -> bool {
+    path.components().any(|c| match c {
+        Component::Prefix(_) => true,
+        Component::RootDir   => true,
+        Component::ParentDir => true,
+        Component::CurDir    => false,
+        Component::Normal(_) => false,
+    })
+}
+
+/// Joins `path` to `dir`, or returns `BintrayError::UnsafePath` if
+/// `path` could point outside of `dir` (see `is_unsafe_path()`).
+fn join_safe_path<T: AsRef<Path>>(dir: &Path, path: T)
+    -> Result<PathBuf, BintrayError>
+{
+    let path = path.as_ref();
+    if is_unsafe_path(path) {
+        return Err(BintrayError::UnsafePath { path: path.to_path_buf() });
+    }
+    Ok(dir.join(path))
+}
+
 pub fn clean_path<T: AsRef<Path>>(path: T) -> PathBuf {
     let initial_path = Path::new(path.as_ref());
     let cleaned_components = initial_path.components()
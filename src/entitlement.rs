@@ -0,0 +1,66 @@
+//! Bintray entitlements
+//!
+//! An entitlement grants access to a private repository, or a path in
+//! it, to the holders of some download keys.
+
+use std::fmt;
+use std::path::Path;
+
+use content;
+
+/// Representation of an entitlement's attributes.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Entitlement {
+    pub id: String,
+    pub access: EntitlementAccess,
+    /// The path covered by this entitlement, relative to the repository
+    /// root. The whole repository is covered if it is not set.
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub download_keys: Option<Vec<String>>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+/// Access levels an entitlement can grant, from the least to the most
+/// privileged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum EntitlementAccess {
+    /// Download only.
+    #[serde(rename = "r")]
+    Read,
+    /// Download, upload and delete.
+    #[serde(rename = "rw")]
+    ReadWrite,
+}
+
+impl Entitlement {
+    /// Returns true if the given path, relative to the repository root,
+    /// is covered by this entitlement.
+    pub fn covers<T: AsRef<Path>>(&self, path: T) -> bool {
+        match self.path {
+            Some(ref prefix) => {
+                content::clean_path(path)
+                    .starts_with(content::clean_path(prefix))
+            }
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for Entitlement {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}<{}>", self.id, self.access)
+    }
+}
+
+impl fmt::Display for EntitlementAccess {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let access = match *self {
+            EntitlementAccess::Read      => "r",
+            EntitlementAccess::ReadWrite => "rw",
+        };
+        write!(f, "{}", access)
+    }
+}
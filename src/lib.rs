@@ -11,12 +11,18 @@ extern crate hyper_rustls;
 #[macro_use] extern crate log;
 #[macro_use] extern crate serde_derive;
 extern crate serde_json;
+extern crate sha1;
+extern crate sha2;
+extern crate url;
 extern crate version_compare;
 
 #[macro_use] pub mod utils;
 
 pub mod client;
+pub mod subject;
 pub mod repository;
 pub mod package;
 pub mod version;
 pub mod content;
+pub mod entitlement;
+pub mod mock;
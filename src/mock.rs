@@ -0,0 +1,407 @@
+//! Offline transport for examples and tests
+//!
+//! A `MockTransport` serves canned responses instead of talking to
+//! Bintray. A `BintrayClient` created with `BintrayClient::mock()` uses
+//! it, so the API can be demonstrated or tested without network
+//! access.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use bintray::client::BintrayClient;
+//! use bintray::mock::{MockResponse, MockTransport};
+//! use bintray::repository::{Repository, RepositoryType};
+//! use hyper::method::Method;
+//! # extern crate bintray;
+//! # extern crate hyper;
+//!
+//! let transport = MockTransport::new()
+//!     .on(Method::Get, "/repos/my-company/my-repo",
+//!         MockResponse::new(200, r#"{
+//!             "owner": "my-company",
+//!             "name": "my-repo",
+//!             "type": "rpm",
+//!             "premium": false,
+//!             "created": "2017-03-01T12:34:56.789Z",
+//!             "package_count": 0,
+//!             "private": false,
+//!             "gpg_sign_metadata": false,
+//!             "gpg_sign_files": false,
+//!             "gpg_use_owner_key": false
+//!         }"#));
+//! let client = BintrayClient::mock(None, None, transport.clone());
+//!
+//! let mut repository = Repository::new("my-company", "my-repo");
+//! assert!(repository.get(&client).is_ok());
+//! assert_eq!(repository.type_, RepositoryType::Rpm);
+//!
+//! let requests = transport.get_requests();
+//! assert_eq!(requests.len(), 1);
+//! assert_eq!(requests[0].path, "/repos/my-company/my-repo");
+//! ```
+
+use hyper;
+use hyper::method::Method;
+use hyper::net::{NetworkConnector, NetworkStream};
+use hyper::status::StatusCode;
+use std::cmp;
+use std::io::{self, Cursor, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use utils::Clock;
+
+/// A set of canned responses, indexed by request method and path.
+///
+/// Cloning a `MockTransport` is cheap and the clones share the
+/// recorded requests.
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    routes: Arc<Mutex<Vec<(Method, String, MockResponse)>>>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+/// A canned response.
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// See `MockResponse::accept_ranges()`.
+    pub accept_ranges: bool,
+}
+
+/// A request received by a `MockTransport`.
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: Method,
+    /// The host the request was sent to.
+    pub host: String,
+    /// The path, including the query string if any.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockTransport {
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Registers the response to return for the given method and path.
+    ///
+    /// The path may include a query string. In this case, it must match
+    /// the request exactly. Otherwise, the query string of the request
+    /// is ignored. When several responses match a request, they are
+    /// returned in the order they were registered, one per request; the
+    /// last one is then returned for all subsequent requests.
+    ///
+    /// A request without a matching response gets a "404 Not Found".
+    pub fn on(self, method: Method, path: &str, response: MockResponse)
+        -> MockTransport
+    {
+        self.routes.lock().unwrap()
+            .push((method, String::from(path), response));
+        self
+    }
+
+    /// Returns the requests received so far, oldest first.
+    pub fn get_requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn respond(&self, request: MockRequest) -> MockResponse {
+        let response = {
+            let mut routes = self.routes.lock().unwrap();
+            let matching: Vec<usize> = routes.iter()
+                .enumerate()
+                .filter(|&(_, (method, path, _))| {
+                    *method == request.method &&
+                        (*path == request.path ||
+                         *path == request.path_without_query())
+                })
+                .map(|(index, _)| index)
+                .collect();
+            match matching.len() {
+                0 => None,
+                1 => Some(routes[matching[0]].2.clone()),
+                _ => Some(routes.remove(matching[0]).2),
+            }
+        };
+
+        let response = response.unwrap_or_else(|| {
+            MockResponse::new(404, &format!(
+                    r#"{{"message": "No mock response for {} {}"}}"#,
+                    request.method, request.path))
+        });
+        let response = match request.get_header("Range") {
+            Some(range) if response.accept_ranges &&
+                response.status == 200 => {
+                    response.to_range(range).unwrap_or(response)
+                }
+            _ => response,
+        };
+
+        debug!("MockTransport: {} {} -> {}",
+               request.method, request.path, response.status);
+        self.requests.lock().unwrap().push(request);
+        response
+    }
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> MockResponse {
+        MockResponse {
+            status,
+            headers: vec![],
+            body: body.as_bytes().to_vec(),
+            accept_ranges: false,
+        }
+    }
+
+    /// Serves the requested part of the body, with a "206 Partial
+    /// Content" status, to requests with a single-range `Range` header
+    /// (e.g. `bytes=0-1023`).
+    pub fn accept_ranges(mut self) -> MockResponse {
+        self.accept_ranges = true;
+        self
+    }
+
+    fn to_range(&self, range: &str) -> Option<MockResponse> {
+        let range = range.trim();
+        if !range.starts_with("bytes=") {
+            return None;
+        }
+        let mut bounds = range["bytes=".len()..].splitn(2, '-');
+        let start: usize = bounds.next()?.trim().parse().ok()?;
+        let end = match bounds.next()?.trim() {
+            ""  => self.body.len().checked_sub(1)?,
+            end => cmp::min(end.parse().ok()?, self.body.len().checked_sub(1)?),
+        };
+        if start > end {
+            return None;
+        }
+
+        let mut response = MockResponse::new(206, "")
+            .header("Content-Range", &format!(
+                    "bytes {}-{}/{}", start, end, self.body.len()));
+        response.headers.extend(self.headers.iter().cloned());
+        response.body = self.body[start..end + 1].to_vec();
+        Some(response)
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, name: &str, value: &str) -> MockResponse {
+        self.headers.push((String::from(name), String::from(value)));
+        self
+    }
+
+    fn to_bytes(&self, with_body: bool) -> Vec<u8> {
+        let status = StatusCode::from_u16(self.status);
+        let mut bytes = format!(
+            "HTTP/1.1 {} {}\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n",
+            self.status,
+            status.canonical_reason().unwrap_or("Unknown"),
+            self.body.len()).into_bytes();
+        for (name, value) in &self.headers {
+            bytes.extend(format!("{}: {}\r\n", name, value).into_bytes());
+        }
+        bytes.extend(b"\r\n");
+        if with_body {
+            bytes.extend(&self.body);
+        }
+        bytes
+    }
+}
+
+impl MockRequest {
+    /// Returns the value of a request header.
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the path, without the query string.
+    pub fn path_without_query(&self) -> &str {
+        self.path.split('?').next().unwrap()
+    }
+
+    fn parse(host: &str, raw: &[u8]) -> io::Result<MockRequest> {
+        let invalid = || {
+            io::Error::new(io::ErrorKind::InvalidData, "Invalid HTTP request")
+        };
+
+        let header_end = raw.windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(invalid)?;
+        let head = str::from_utf8(&raw[..header_end])
+            .map_err(|_| invalid())?;
+        let mut lines = head.split("\r\n");
+
+        let mut request_line = lines.next().ok_or_else(invalid)?.split(' ');
+        let method = request_line.next().ok_or_else(invalid)?
+            .parse::<Method>().map_err(|_| invalid())?;
+        let path = request_line.next().ok_or_else(invalid)?;
+
+        let headers = lines
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, ':');
+                match (parts.next(), parts.next()) {
+                    (Some(name), Some(value)) => {
+                        Some((String::from(name.trim()),
+                              String::from(value.trim())))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+
+        let mut request = MockRequest {
+            method,
+            host: String::from(host),
+            path: String::from(path),
+            headers,
+            body: raw[header_end + 4..].to_vec(),
+        };
+
+        let chunked = request.get_header("Transfer-Encoding")
+            .map_or(false, |value| value.eq_ignore_ascii_case("chunked"));
+        if chunked {
+            request.body = dechunk(&request.body).ok_or_else(invalid)?;
+        }
+
+        Ok(request)
+    }
+}
+
+fn dechunk(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut body = vec![];
+    loop {
+        let line_end = raw.windows(2).position(|w| w == b"\r\n")?;
+        let size = str::from_utf8(&raw[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.trim(), 16).ok()?;
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        if raw.len() < size + 2 {
+            return None;
+        }
+        body.extend(&raw[..size]);
+        raw = &raw[size + 2..];
+    }
+}
+
+impl NetworkConnector for MockTransport {
+    type Stream = MockStream;
+
+    fn connect(&self, host: &str, _port: u16, _scheme: &str)
+        -> hyper::Result<MockStream>
+    {
+        Ok(MockStream {
+            transport: self.clone(),
+            host: String::from(host),
+            request: vec![],
+            response: None,
+        })
+    }
+}
+
+/// The connection to a `MockTransport`.
+///
+/// The request is buffered until the response is read.
+pub struct MockStream {
+    transport: MockTransport,
+    host: String,
+    request: Vec<u8>,
+    response: Option<Cursor<Vec<u8>>>,
+}
+
+impl Read for MockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.response.is_none() {
+            let request = MockRequest::parse(&self.host, &self.request)?;
+            let with_body = request.method != Method::Head;
+            let response = self.transport.respond(request);
+            self.response = Some(Cursor::new(response.to_bytes(with_body)));
+        }
+
+        self.response.as_mut().unwrap().read(buf)
+    }
+}
+
+impl Write for MockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.request.extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl NetworkStream for MockStream {
+    fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        Ok(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0)))
+    }
+
+    fn set_read_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A clock which only advances when something sleeps on it.
+///
+/// It is meant to be passed to `utils::wait_for_condition_with_clock()`
+/// to test timeouts instantly. Cloning a `MockClock` is cheap and the
+/// clones share the same time.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    start: Instant,
+    sleeps: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl MockClock {
+    pub fn new() -> MockClock {
+        MockClock {
+            start: Instant::now(),
+            sleeps: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Returns the time slept so far.
+    pub fn elapsed(&self) -> Duration {
+        self.sleeps.lock().unwrap().iter().sum()
+    }
+
+    /// Returns the durations of the sleeps so far, oldest first.
+    pub fn get_sleeps(&self) -> Vec<Duration> {
+        self.sleeps.lock().unwrap().clone()
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> MockClock {
+        MockClock::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.sleeps.lock().unwrap().push(duration);
+    }
+}
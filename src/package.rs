@@ -1,14 +1,14 @@
 use hyper::status::StatusCode;
 use serde_json;
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 use std::io::{self, Read};
-use version_compare;
 
 use client::{BintrayClient, BintrayError};
-use version::Version;
-use content::Content;
+use version::{Version, compare_versions};
+use content::{self, Content};
 use utils;
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
@@ -70,6 +70,27 @@ pub struct Package {
     pub attribute_names: Option<Vec<String>>,
     #[serde(skip_serializing_if="Option::is_none")]
     pub attributes: Option<HashMap<String, String>>,
+
+    #[serde(skip_deserializing)]
+    pub version_sort_strategy: VersionSortStrategy,
+    /// True if only the name is known, e.g. for packages returned by
+    /// `Repository::packages()`, until `get()` is called.
+    #[serde(skip_deserializing)]
+    pub partial: bool,
+}
+
+/// How the versions of a package are sorted.
+///
+/// Other orderings can be used with `Package::sorted_versions_by()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VersionSortStrategy {
+    /// Versions are compared as version numbers, falling back to
+    /// string comparison (see `version::compare_versions()`). This is
+    /// the default.
+    #[default]
+    Semver,
+    /// Versions are compared as strings.
+    Lexicographic,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -118,9 +139,25 @@ impl Package {
 
             attribute_names: None,
             attributes: None,
+
+            version_sort_strategy: VersionSortStrategy::Semver,
+            partial: false,
         }
     }
 
+    /// Same as `new()`, but checks names with `utils::validate_name()`
+    /// first, so obviously invalid names are rejected before reaching
+    /// Bintray.
+    pub fn try_new(owner: &str, repository: &str, package: &str)
+        -> Result<Package, BintrayError>
+    {
+        utils::validate_name(owner)?;
+        utils::validate_name(repository)?;
+        utils::validate_name(package)?;
+
+        Ok(Package::new(owner, repository, package))
+    }
+
     pub fn set_licenses<T: Borrow<str>>(mut self, licenses: &[T]) -> Package {
         let mut licenses = licenses.iter()
             .map(|s| String::from(s.borrow()))
@@ -131,11 +168,61 @@ impl Package {
         self
     }
 
+    /// Same as `set_licenses()`, with licenses given as a
+    /// comma-separated list (e.g. `MPL-1.1, Apache-2.0`).
+    pub fn set_licenses_csv(self, licenses: &str) -> Package {
+        self.set_licenses(&utils::split_csv(licenses))
+    }
+
+    pub fn set_labels<T: Borrow<str>>(mut self, labels: &[T]) -> Package {
+        let mut labels = labels.iter()
+            .map(|s| String::from(s.borrow()))
+            .collect::<Vec<_>>();
+        labels.sort();
+
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Same as `set_labels()`, with labels given as a comma-separated
+    /// list (e.g. `label-1, label-2`).
+    pub fn set_labels_csv(self, labels: &str) -> Package {
+        self.set_labels(&utils::split_csv(labels))
+    }
+
     pub fn set_vcs_url(mut self, vcs_url: &str) -> Package {
         self.vcs_url = Some(String::from(vcs_url));
         self
     }
 
+    /// Sets how versions are sorted, for packages not following a
+    /// strict version numbering scheme (e.g. date-based versions).
+    pub fn set_version_sort_strategy(mut self, strategy: VersionSortStrategy)
+        -> Package
+    {
+        self.version_sort_strategy = strategy;
+        self
+    }
+
+    /// Returns the versions fetched with `get()`, oldest first,
+    /// according to the package's version sort strategy.
+    ///
+    /// `versions` is left in the order returned by Bintray.
+    pub fn sorted_versions(&self) -> Vec<String> {
+        let strategy = self.version_sort_strategy;
+        self.sorted_versions_by(|a, b| strategy.compare(a, b))
+    }
+
+    /// Same as `sorted_versions()`, with versions compared by `compare`
+    /// instead of the package's version sort strategy.
+    pub fn sorted_versions_by<F>(&self, mut compare: F) -> Vec<String>
+        where F: FnMut(&str, &str) -> Ordering
+    {
+        let mut versions = self.versions.clone();
+        versions.sort_by(|a, b| compare(a, b));
+        versions
+    }
+
     pub fn get(&mut self, get_attribute_values: bool, client: &BintrayClient)
         -> Result<(), BintrayError>
     {
@@ -195,17 +282,18 @@ impl Package {
                 self.vcs_url = queried.vcs_url;
                 self.github_repo = queried.github_repo
                     .and_then(|s| {
-                        if s.is_empty() { None } else {  Some(s) }
+                        if s.is_empty() { None } else { Some(s) }
                     });
                 self.github_release_notes_file =
                     queried.github_release_notes_file
                     .and_then(|s| {
-                        if s.is_empty() { None } else {  Some(s) }
+                        if s.is_empty() { None } else { Some(s) }
                     });
 
                 self.attribute_names = queried.attribute_names
                     .map(|mut v| { v.sort(); v });
                 self.attributes = queried.attributes;
+                self.partial = false;
 
                 self.versions.reverse();
 
@@ -218,10 +306,8 @@ impl Package {
                     "Package not found", true)
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "GetPackage",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetPackage", client)
             }
             _ => {
                 report_bintray_error!(
@@ -327,10 +413,8 @@ impl Package {
                 Ok(())
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "CreatePackage",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "CreatePackage", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -422,10 +506,8 @@ impl Package {
                 Ok(())
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "UpdatePackage",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "UpdatePackage", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -468,10 +550,8 @@ impl Package {
                     self, resp, body, "DeletePackage")
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "DeletePackage",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "DeletePackage", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -530,13 +610,10 @@ impl Package {
                     .collect::<Vec<Version>>()
             }
             &Some(ref oldest) => {
-                let parsed_oldest =
-                    version_compare::Version::from(oldest).unwrap();
                 self.versions.iter()
                     .skip_while(|&version| {
-                        let parsed_version =
-                            version_compare::Version::from(&version).unwrap();
-                        parsed_version < parsed_oldest
+                        self.version_sort_strategy
+                            .compare(version, oldest) == Ordering::Less
                     })
                     .map(filter)
                     .collect::<Vec<Version>>()
@@ -544,6 +621,91 @@ impl Package {
         }
     }
 
+    /// Queries every version of this package and returns them, sorted
+    /// according to the package's version sort strategy.
+    ///
+    /// The list of versions is the one fetched with `get()`. This costs
+    /// one request per version: up to `concurrency` requests are sent in
+    /// parallel. The first error, if any, is returned.
+    pub fn versions_with_metadata(&self,
+                                  get_attribute_values: bool,
+                                  concurrency: usize,
+                                  client: &BintrayClient)
+        -> Result<Vec<Version>, BintrayError>
+    {
+        let mut versions = utils::parallel_map(
+            &self.versions, concurrency, |name| {
+                let mut version = Version::new(
+                    &self.owner,
+                    &self.repository,
+                    &self.package,
+                    name);
+                version.get(get_attribute_values, client).map(|()| version)
+            })
+            .into_iter()
+            .collect::<Result<Vec<Version>, BintrayError>>()?;
+        versions.sort_by(|a, b| {
+            self.version_sort_strategy.compare(&a.version, &b.version)
+        });
+        Ok(versions)
+    }
+
+    /// Returns the names of the versions of this package having the
+    /// given label (e.g. `latest-stable`), sorted according to the
+    /// package's version sort strategy.
+    ///
+    /// Bintray can't filter versions by label, so this queries every
+    /// version, like `versions_with_metadata()`.
+    pub fn versions_with_label(&self,
+                               label: &str,
+                               concurrency: usize,
+                               client: &BintrayClient)
+        -> Result<Vec<String>, BintrayError>
+    {
+        let versions = self.versions_with_metadata(false, concurrency, client)?;
+        Ok(versions.into_iter()
+           .filter(|version| {
+               version.labels.as_ref()
+                   .map_or(false, |labels| labels.iter().any(|l| l == label))
+           })
+           .map(|version| version.version)
+           .collect())
+    }
+
+    /// Returns the files of every version of this package, published
+    /// or not, each paired with the name of its version.
+    ///
+    /// The list of versions is the one fetched with `get()`. The files
+    /// of each version are listed with one request per version: up to
+    /// `concurrency` requests are sent in parallel. The result is
+    /// sorted according to the package's version sort strategy. The
+    /// first error, if any, is returned.
+    pub fn all_files(&self, concurrency: usize, client: &BintrayClient)
+        -> Result<Vec<(String, Content)>, BintrayError>
+    {
+        let versions = self.sorted_versions();
+
+        let results = utils::parallel_map(&versions, concurrency, |version| {
+            self.list_files(Some(version), true, client)
+        });
+
+        let mut files = vec![];
+        for (version, result) in versions.iter().zip(results) {
+            files.extend(result?.into_iter()
+                         .map(|file| (version.clone(), file)));
+        }
+        Ok(files)
+    }
+
+    /// Returns the total size, in bytes, of the files of all versions
+    /// of this package, published or not.
+    pub fn total_size(&self, client: &BintrayClient)
+        -> Result<u64, BintrayError>
+    {
+        let files = self.list_files(None, true, client)?;
+        Ok(content::total_size(&files))
+    }
+
     pub fn list_files(&self,
                       version: Option<&str>,
                       include_unpublished: bool,
@@ -611,10 +773,8 @@ impl Package {
                 Ok(files)
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "GetFiles",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetFiles", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -638,6 +798,16 @@ impl fmt::Display for Package {
     }
 }
 
+impl VersionSortStrategy {
+    /// Compares two version strings according to this strategy.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match *self {
+            VersionSortStrategy::Semver        => compare_versions(a, b),
+            VersionSortStrategy::Lexicographic => a.cmp(b),
+        }
+    }
+}
+
 impl From<String> for PackageMaturity {
     fn from(from: String) -> PackageMaturity {
         match from.to_lowercase().trim() {
@@ -4,14 +4,37 @@
 //! repository:
 //! * querying a repository;
 //! * creating a repository;
-//! * updating a repository.
+//! * updating a repository;
+//! * making a repository private or public;
+//! * creating or updating a repository as needed;
+//! * calculating a repository metadata;
+//! * creating a repository with the settings of another one;
+//! * listing packages, sorted by name or date;
+//! * listing entitlements;
+//! * listing versions past their TTL;
+//! * verifying published files against a manifest;
+//! * reporting which versions are signed;
+//! * fetching and installing the GPG public key;
+//! * listing files left unpublished for a long time.
 
+use hyper::client::response::Response;
 use hyper::status::StatusCode;
+use hyper::Url;
 use serde_json;
+use std::borrow::Borrow;
 use std::fmt;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-use client::{BintrayClient, BintrayError};
+use client::{BintrayClient, BintrayError, OperationStatus};
+use content::{self, Content};
+use entitlement::Entitlement;
+use package::Package;
+use subject::Subject;
+use version::Version;
 use utils;
 
 /// Representation of a repository's attributes.
@@ -117,6 +140,46 @@ pub enum RepositoryType {
     Deb,
 }
 
+/// An entry of the package listing of a repository.
+#[derive(Deserialize)]
+struct PackageListEntry {
+    name: String,
+    #[serde(default)]
+    linked: bool,
+}
+
+/// Properties sent by `Repository::update()` and
+/// `Repository::set_privacy()`. Unset properties are left unchanged.
+#[derive(Default, Serialize)]
+struct UpdateRepositoryReq {
+    #[serde(skip_serializing_if="Option::is_none")]
+    business_unit: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    desc: Option<String>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    gpg_sign_metadata: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    gpg_sign_files: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    gpg_use_owner_key: Option<bool>,
+    #[serde(skip_serializing_if="Option::is_none")]
+    private: Option<bool>,
+}
+
+/// Orders in which `Repository::list_packages_ordered()` can return
+/// packages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackageOrder {
+    /// Alphabetical order of package names.
+    Name,
+    /// Most recently created packages first.
+    CreatedDesc,
+    /// Most recently updated packages first.
+    UpdatedDesc,
+}
+
 impl Repository {
     /// Instanciates a new `Repository` structure.
     ///
@@ -147,6 +210,44 @@ impl Repository {
         }
     }
 
+    /// Same as `new()`, but checks names with `utils::validate_name()`
+    /// first, so obviously invalid names are rejected before reaching
+    /// Bintray.
+    pub fn try_new(owner: &str, repository: &str)
+        -> Result<Repository, BintrayError>
+    {
+        utils::validate_name(owner)?;
+        utils::validate_name(repository)?;
+
+        Ok(Repository::new(owner, repository))
+    }
+
+    /// Sets the labels attached to this repository. They are sorted.
+    pub fn set_labels<T: Borrow<str>>(mut self, labels: &[T]) -> Repository {
+        let mut labels = labels.iter()
+            .map(|s| String::from(s.borrow()))
+            .collect::<Vec<_>>();
+        labels.sort();
+
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Same as `set_labels()`, with labels given as a comma-separated
+    /// list (e.g. `label-1, label-2`).
+    pub fn set_labels_csv(self, labels: &str) -> Repository {
+        self.set_labels(&utils::split_csv(labels))
+    }
+
+    /// Sets the business unit associated with this repository.
+    ///
+    /// Only business accounts support business units. Leave it unset
+    /// for other subjects, so the field is not submitted.
+    pub fn set_business_unit(mut self, business_unit: &str) -> Repository {
+        self.business_unit = Some(String::from(business_unit));
+        self
+    }
+
     /// Queries Bintray API and update `Repository` structure with the
     /// returned attributes.
     ///
@@ -205,6 +306,7 @@ impl Repository {
 
         let mut body = String::new();
         resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
 
         match resp {
             _ if resp.status == StatusCode::Ok => {
@@ -249,10 +351,8 @@ impl Repository {
                     "Repository not found", true)
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "GetRepository",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetRepository", client)
             }
             _ => {
                 report_bintray_error!(
@@ -443,6 +543,7 @@ impl Repository {
 
         let mut body = String::new();
         resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
 
         match resp {
             _ if resp.status == StatusCode::Created => {
@@ -455,10 +556,8 @@ impl Repository {
                 Ok(())
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "CreateRepository",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "CreateRepository", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -466,6 +565,12 @@ impl Repository {
                     io::ErrorKind::PermissionDenied,
                     "Requires admin privileges")
             }
+            _ if resp.status == StatusCode::Conflict => {
+                report_bintray_error!(
+                    self, resp, body, "CreateRepository",
+                    io::ErrorKind::AlreadyExists,
+                    "Repository already exists")
+            }
             _ => {
                 report_bintray_error!(
                     self, resp, body, "CreateRepository",
@@ -529,26 +634,14 @@ impl Repository {
             path.push(&self.repository);
         }
 
-        #[derive(Serialize)]
-        struct UpdateRepositoryReq {
-            #[serde(skip_serializing_if="Option::is_none")]
-            business_unit: Option<String>,
-            #[serde(skip_serializing_if="Option::is_none")]
-            desc: Option<String>,
-            #[serde(skip_serializing_if="Option::is_none")]
-            labels: Option<Vec<String>>,
-            gpg_sign_metadata: bool,
-            gpg_sign_files: bool,
-            gpg_use_owner_key: bool,
-        };
-
         let args = UpdateRepositoryReq {
             business_unit: self.business_unit.clone(),
             desc: self.desc.clone(),
             labels: self.labels.clone(),
-            gpg_sign_metadata: self.gpg_sign_metadata,
-            gpg_sign_files: self.gpg_sign_files,
-            gpg_use_owner_key: self.gpg_use_owner_key,
+            gpg_sign_metadata: Some(self.gpg_sign_metadata),
+            gpg_sign_files: Some(self.gpg_sign_files),
+            gpg_use_owner_key: Some(self.gpg_use_owner_key),
+            private: None,
         };
 
         let json = serde_json::to_string_pretty(&args)?;
@@ -562,10 +655,12 @@ impl Repository {
 
         let mut body = String::new();
         resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
 
         match resp {
             _ if resp.status == StatusCode::Ok => {
                 info!("UpdateRepository({}): {}", self, body);
+                client.forget_repository(&self.owner, &self.repository);
                 Ok(())
             }
             _ if resp.status == StatusCode::NotFound => {
@@ -575,26 +670,235 @@ impl Repository {
                     "Repository not found")
             }
             _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "UpdateRepository", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
                     self, resp, body, "UpdateRepository",
                     io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                    "Requires admin privileges")
             }
-            _ if resp.status == StatusCode::Forbidden => {
+            _ => {
                 report_bintray_error!(
                     self, resp, body, "UpdateRepository",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Makes the repository private or public.
+    ///
+    /// Only the privacy is updated: `update()` never changes it, so a
+    /// repository can't be made public by mistake. Bintray requires a
+    /// premium account for private repositories; when the server
+    /// refuses the change for this reason, this is reported as an
+    /// `io::ErrorKind::PermissionDenied` error saying so.
+    pub fn set_privacy(&mut self, private: bool, client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        let mut url = client.get_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.push("repos");
+            path.push(&self.owner);
+            path.push(&self.repository);
+        }
+
+        let args = UpdateRepositoryReq {
+            private: Some(private),
+            ..UpdateRepositoryReq::default()
+        };
+
+        let json = serde_json::to_string_pretty(&args)?;
+        info!(
+            "SetRepositoryPrivacy({}): Submitting the following properties:\n{}",
+            self, json);
+
+        let mut resp = client.patch(url)
+            .body(&json)
+            .send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("SetRepositoryPrivacy({}): {}", self, body);
+                self.private = private;
+                client.forget_repository(&self.owner, &self.repository);
+                Ok(())
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "SetRepositoryPrivacy",
+                    io::ErrorKind::NotFound,
+                    "Repository not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "SetRepositoryPrivacy", client)
+            }
+            _ if private &&
+                (resp.status == StatusCode::BadRequest ||
+                 resp.status == StatusCode::Forbidden) &&
+                utils::get_bintray_error_message(
+                    body.clone(), resp.status, "")
+                .to_lowercase().contains("premium") => {
+                report_bintray_error!(
+                    self, resp, body, "SetRepositoryPrivacy",
+                    io::ErrorKind::PermissionDenied,
+                    "Private repositories require a premium account")
+            }
+            _ if resp.status == StatusCode::Forbidden => {
+                report_bintray_error!(
+                    self, resp, body, "SetRepositoryPrivacy",
                     io::ErrorKind::PermissionDenied,
                     "Requires admin privileges")
             }
             _ => {
                 report_bintray_error!(
-                    self, resp, body, "UpdateRepository",
+                    self, resp, body, "SetRepositoryPrivacy",
                     io::ErrorKind::Other,
                     "Unrecognized error")
             }
         }
     }
 
+    /// Makes sure the repository exists with the settings of this
+    /// structure.
+    ///
+    /// The repository is created if it doesn't exist. Otherwise, it is
+    /// updated if its settings differ; unset settings (e.g. `labels`
+    /// left to `None`) are not compared. Note that the type and the
+    /// Debian and YUM settings of an existing repository can't be
+    /// updated. In the end, the structure is updated with the
+    /// attributes returned by Bintray, like `get()` does.
+    ///
+    /// Returns true if the repository was created.
+    pub fn ensure(&mut self, client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        let mut live = Repository::new(&self.owner, &self.repository);
+        let created = if live.exists(client)? {
+            if live.type_ != self.type_ {
+                warn!("EnsureRepository({}): Existing repository has type {}",
+                      self, live.type_);
+            }
+            self.update_if_different(&live, client)?;
+            false
+        } else {
+            match self.create(client) {
+                Ok(()) => true,
+                Err(BintrayError::Io(ref e))
+                    if e.kind() == io::ErrorKind::AlreadyExists => {
+                        // Created concurrently.
+                        live.get(client)?;
+                        self.update_if_different(&live, client)?;
+                        false
+                    }
+                Err(e) => return Err(e),
+            }
+        };
+
+        self.get(client)?;
+        Ok(created)
+    }
+
+    fn update_if_different(&self, live: &Repository, client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        // `update()` doesn't send unset properties, so they can't
+        // differ.
+        let different =
+            (self.business_unit.is_some() &&
+             self.business_unit != live.business_unit) ||
+            (self.desc.is_some() && self.desc != live.desc) ||
+            (self.labels.is_some() && self.labels != live.labels) ||
+            self.gpg_sign_metadata != live.gpg_sign_metadata ||
+            self.gpg_sign_files != live.gpg_sign_files ||
+            self.gpg_use_owner_key != live.gpg_use_owner_key;
+
+        if different {
+            self.update(client)
+        } else {
+            info!("EnsureRepository({}): Already up-to-date", self);
+            Ok(())
+        }
+    }
+
+    /// Creates a new repository named `new_name` with the same settings
+    /// as this one.
+    ///
+    /// The settings are queried from Bintray first, so the `Repository`
+    /// structure members may be left uninitialized. Only the settings
+    /// are copied, not the packages. The created `Repository` is
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// Create a staging repository with the settings of a production
+    /// one:
+    ///
+    /// ```rust
+    /// use bintray::client::BintrayClient;
+    /// use bintray::repository::{Repository, RepositoryType};
+    /// # use std::env;
+    /// # let username = env::var("BINTRAY_USERNAME").ok();
+    /// # let api_key = env::var("BINTRAY_API_KEY").ok();
+    /// # let owner = env::var("BINTRAY_OWNER")
+    /// #     .unwrap_or(String::from("my-company"));
+    /// # let repository_name =
+    /// #     "t-bintray-crate-repository-7";
+    /// # let new_repository_name =
+    /// #     "t-bintray-crate-repository-8";
+    ///
+    /// // Initialize a Bintray client with a username and an API key.
+    /// // Those are `Option` because some operations can be performed
+    /// // anonymously.
+    /// let client = BintrayClient::new(username, api_key);
+    ///
+    /// # {
+    /// #   let mut repository = Repository::new(&owner, &repository_name);
+    /// #   repository.type_ = RepositoryType::Rpm;
+    /// #   repository.yum_metadata_depth = Some(2);
+    /// #   assert!(repository.delete(&client).is_ok());
+    /// #   assert!(repository.create(&client).is_ok());
+    /// #   let mut repository = Repository::new(&owner, &new_repository_name);
+    /// #   assert!(repository.delete(&client).is_ok());
+    /// # }
+    /// let repository = Repository::new(&owner, &repository_name);
+    /// let mut new_repository = repository
+    ///     .clone_settings(&new_repository_name, &client)
+    ///     .unwrap();
+    ///
+    /// // We can query it again to double-check the settings are the same.
+    /// assert!(new_repository.get(&client).is_ok());
+    /// assert_eq!(new_repository.type_, RepositoryType::Rpm);
+    /// assert_eq!(new_repository.yum_metadata_depth, Some(2));
+    /// # assert!(new_repository.delete(&client).is_ok());
+    /// # let mut repository = repository;
+    /// # assert!(repository.delete(&client).is_ok());
+    /// ```
+    pub fn clone_settings(&self, new_name: &str, client: &BintrayClient)
+        -> Result<Repository, BintrayError>
+    {
+        let mut source = Repository::new(&self.owner, &self.repository);
+        source.get(client)?;
+
+        let mut repository = source.clone();
+        repository.repository = String::from(new_name);
+        repository.created = None;
+        repository.package_count = 0;
+
+        info!("CloneRepositorySettings({}): Creating {}", source, repository);
+        repository.create(client)?;
+
+        Ok(repository)
+    }
+
     /// Deletes a repository.
     ///
     /// The `Repository` structure members may be left uninitialized.
@@ -645,9 +949,11 @@ impl Repository {
 
         let mut resp = client.delete(url)
             .send()?;
+        client.forget_repository(&self.owner, &self.repository);
 
         let mut body = String::new();
         resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
 
         match resp {
             _ if resp.status == StatusCode::Ok => {
@@ -665,20 +971,152 @@ impl Repository {
                 Ok(())
             }
             _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "DeleteRepository", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
                     self, resp, body, "DeleteRepository",
                     io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                    "Requires admin privileges")
             }
-            _ if resp.status == StatusCode::Forbidden => {
+            _ => {
                 report_bintray_error!(
                     self, resp, body, "DeleteRepository",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Schedules the calculation of the repository metadata.
+    ///
+    /// The calculation is usually asynchronous: Bintray accepts the
+    /// request and returns immediately, in which case
+    /// `OperationStatus::Pending` is returned. If the repository
+    /// metadata are signed, the GPG passphrase configured on the
+    /// `BintrayClient` is sent along.
+    pub fn calc_metadata(&self, client: &BintrayClient)
+        -> Result<OperationStatus<()>, BintrayError>
+    {
+        let mut url = client.get_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.push("calc_metadata");
+            path.push(&self.owner);
+            path.push(&self.repository);
+        }
+
+        let mut resp = client.post(url)
+            .add_client_gpg_passphrase()
+            .send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("CalcRepositoryMetadata({}): {}", self, body);
+                Ok(OperationStatus::Done(()))
+            }
+            _ if resp.status == StatusCode::Accepted => {
+                info!("CalcRepositoryMetadata({}): Pending: {}", self, body);
+                Ok(OperationStatus::Pending)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "CalcRepositoryMetadata",
+                    io::ErrorKind::NotFound,
+                    "Repository not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "CalcRepositoryMetadata", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
+                report_bintray_error!(
+                    self, resp, body, "CalcRepositoryMetadata",
                     io::ErrorKind::PermissionDenied,
                     "Requires admin privileges")
             }
             _ => {
                 report_bintray_error!(
-                    self, resp, body, "DeleteRepository",
+                    self, resp, body, "CalcRepositoryMetadata",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Returns the current number of packages provided by this
+    /// repository, as reported by Bintray.
+    ///
+    /// Unlike `get()`, the `Repository` structure is left untouched, so
+    /// the returned value can be compared to the `package_count` field
+    /// to detect a change.
+    pub fn package_count_live(&self, client: &BintrayClient)
+        -> Result<u64, BintrayError>
+    {
+        let mut queried = Repository::new(&self.owner, &self.repository);
+        queried.get(client)?;
+
+        Ok(queried.package_count)
+    }
+
+    /// Returns the type of this repository, as reported by Bintray.
+    ///
+    /// Unlike `get()`, only the type is parsed from the response, so
+    /// other attributes this crate doesn't know about can't make it
+    /// fail. The `Repository` structure is left untouched. The `Deb`
+    /// type is reported as `Debian`.
+    pub fn get_type(&self, client: &BintrayClient)
+        -> Result<RepositoryType, BintrayError>
+    {
+        let mut url = client.get_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.push("repos");
+            path.push(&self.owner);
+            path.push(&self.repository);
+        }
+
+        #[derive(Deserialize)]
+        struct GetRepositoryTypeResp {
+            #[serde(rename = "type")]
+            type_: RepositoryType,
+        }
+
+        let mut resp = client.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("GetRepositoryType({}): {}", self, body);
+
+                let result: GetRepositoryTypeResp =
+                    serde_json::from_str(&body)?;
+                match result.type_ {
+                    RepositoryType::Deb => Ok(RepositoryType::Debian),
+                    type_               => Ok(type_),
+                }
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "GetRepositoryType",
+                    io::ErrorKind::NotFound,
+                    "Repository not found", true)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetRepositoryType", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "GetRepositoryType",
                     io::ErrorKind::Other,
                     "Unrecognized error")
             }
@@ -732,6 +1170,47 @@ impl Repository {
     /// ```
     pub fn list_packages(&self, client: &BintrayClient)
         -> Result<Vec<String>, BintrayError>
+    {
+        let packages = self.list_package_entries(client)?.into_iter()
+            .map(|item| item.name)
+            .collect();
+        Ok(packages)
+    }
+
+    /// Same as `list_packages()`, but returns `Package` structures.
+    ///
+    /// Bintray only reports the names of the packages in this listing:
+    /// the returned packages are flagged as `partial` and only their
+    /// owner, repository and name are set. Call `Package::get()` to
+    /// query the other attributes.
+    pub fn packages(&self, client: &BintrayClient)
+        -> Result<Vec<Package>, BintrayError>
+    {
+        let packages = self.list_package_entries(client)?.into_iter()
+            .map(|item| {
+                let mut package = Package::new(
+                    &self.owner, &self.repository, &item.name);
+                package.partial = true;
+                package
+            })
+            .collect();
+        Ok(packages)
+    }
+
+    /// Same as `list_packages()`, but each name is paired with a flag
+    /// telling if the package is linked from another repository, as
+    /// opposed to owned by this one.
+    pub fn package_names_with_linked(&self, client: &BintrayClient)
+        -> Result<Vec<(String, bool)>, BintrayError>
+    {
+        let packages = self.list_package_entries(client)?.into_iter()
+            .map(|item| (item.name, item.linked))
+            .collect();
+        Ok(packages)
+    }
+
+    fn list_package_entries(&self, client: &BintrayClient)
+        -> Result<Vec<PackageListEntry>, BintrayError>
     {
         let mut url = client.get_base_url();
         {
@@ -742,28 +1221,19 @@ impl Repository {
             path.push("packages");
         }
 
-        #[derive(Deserialize)]
-        struct GetPackagesResp {
-            name: String,
-            // linked: bool,
-        };
-
         let mut resp = client.get(url).send()?;
 
         let mut body = String::new();
         resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
 
         match resp {
             _ if resp.status == StatusCode::Ok => {
                 info!("GetPackages({}): {}", self, body);
 
-                let result: Vec<GetPackagesResp> =
+                let result: Vec<PackageListEntry> =
                     serde_json::from_str(&body)?;
-
-                let packages: Vec<String> = result.into_iter()
-                    .map(|item| item.name)
-                    .collect();
-                Ok(packages)
+                Ok(result)
             }
             _ if resp.status == StatusCode::NotFound => {
                 report_bintray_error!(
@@ -772,19 +1242,561 @@ impl Repository {
                     "Repository not found")
             }
             _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetPackages", client)
+            }
+            _ => {
                 report_bintray_error!(
                     self, resp, body, "GetPackages",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Return the list of packages provided by this repository, sorted
+    /// according to `order`.
+    ///
+    /// Bintray doesn't sort the list of packages, so it is sorted
+    /// locally. To sort by creation or update date, each package is
+    /// queried to get its timestamps.
+    pub fn list_packages_ordered(&self,
+                                 order: PackageOrder,
+                                 client: &BintrayClient)
+        -> Result<Vec<String>, BintrayError>
+    {
+        let mut names = self.list_packages(client)?;
+
+        if order == PackageOrder::Name {
+            names.sort();
+            return Ok(names);
+        }
+
+        let mut packages = vec![];
+        for name in names {
+            let mut package = Package::new(
+                &self.owner, &self.repository, &name);
+            package.get(false, client)?;
+            packages.push(package);
+        }
+
+        /* Timestamps are ISO 8601 strings with the same format, so they
+         * can be compared as strings. Packages without a timestamp are
+         * sorted last. */
+        packages.sort_by(|a, b| {
+            let (a_time, b_time) = match order {
+                PackageOrder::CreatedDesc => (&a.created, &b.created),
+                _ => (&a.updated, &b.updated),
+            };
+            b_time.cmp(a_time).then_with(|| a.package.cmp(&b.package))
+        });
+
+        Ok(packages.into_iter().map(|package| package.package).collect())
+    }
+
+    /// Returns the entitlements defined on this repository.
+    pub fn list_entitlements(&self, client: &BintrayClient)
+        -> Result<Vec<Entitlement>, BintrayError>
+    {
+        let mut url = client.get_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.push("repos");
+            path.push(&self.owner);
+            path.push(&self.repository);
+            path.push("entitlements");
+        }
+
+        let mut resp = client.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+        self.check_being_deleted(&resp, &body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("GetEntitlements({}): {}", self, body);
+
+                let entitlements: Vec<Entitlement> =
+                    serde_json::from_str(&body)?;
+                Ok(entitlements)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "GetEntitlements",
+                    io::ErrorKind::NotFound,
+                    "Repository not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetEntitlements", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
+                report_bintray_error!(
+                    self, resp, body, "GetEntitlements",
                     io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                    "Requires admin privileges")
             }
             _ => {
                 report_bintray_error!(
-                    self, resp, body, "GetPackages",
+                    self, resp, body, "GetEntitlements",
                     io::ErrorKind::Other,
                     "Unrecognized error")
             }
         }
     }
+
+    /// Waits until the repository doesn't exist anymore.
+    ///
+    /// Bintray deletes repositories asynchronously: this is useful
+    /// before recreating a repository which was just deleted.
+    pub fn wait_for_deletion(&self, timeout: Duration, client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        utils::wait_for_condition(self, "WaitForDeletion", timeout, || {
+            let mut repository =
+                Repository::new(&self.owner, &self.repository);
+            match repository.exists(client) {
+                Ok(exists) => Ok(!exists),
+                Err(BintrayError::RepositoryBeingDeleted { .. }) => Ok(false),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    /// Returns `BintrayError::RepositoryBeingDeleted` if Bintray
+    /// refused a request because the repository is being deleted.
+    fn check_being_deleted(&self, resp: &Response, body: &str)
+        -> Result<(), BintrayError>
+    {
+        if !resp.status.is_client_error() {
+            return Ok(());
+        }
+
+        let message = utils::get_bintray_message(String::from(body), "");
+        if message.to_lowercase().contains("being deleted") {
+            warn!("{}: Repository is being deleted: {}", self, message);
+            return Err(BintrayError::RepositoryBeingDeleted {
+                repository: format!("{}/{}", self.owner, self.repository),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the versions, across all packages of this repository,
+    /// whose TTL set with `Version::set_ttl()` is in the past.
+    ///
+    /// Bintray doesn't enforce the TTL: this is meant to be used by a
+    /// cleanup job which then deletes the returned versions. Note that
+    /// this queries every package and version of the repository.
+    pub fn expired_versions(&self, client: &BintrayClient)
+        -> Result<Vec<Version>, BintrayError>
+    {
+        let mut expired = vec![];
+        for package_name in self.list_packages(client)? {
+            let mut package = Package::new(
+                &self.owner, &self.repository, &package_name);
+            package.get(false, client)?;
+
+            for version in package.get_versions(None) {
+                if version.is_expired(client)? {
+                    info!("ExpiredVersions({}): {} expired", self, version);
+                    expired.push(version);
+                }
+            }
+        }
+
+        Ok(expired)
+    }
+
+    /// Tells for each version of each package whether it is signed.
+    ///
+    /// Bintray doesn't report this. For Debian and RPM repositories, a
+    /// version is considered signed if the repository metadata indexing
+    /// its published files is: the `Release` file of the default
+    /// distribution must come with an `InRelease` or `Release.gpg`
+    /// file, and each `repomd.xml` file with a `repomd.xml.asc` file
+    /// (see `Content::index_urls()`). This is what package managers
+    /// verify. A version without published files is not signed.
+    ///
+    /// Other repository types have no signed metadata: a version is
+    /// considered signed if its published files have per-file `.asc`
+    /// signatures, see `content::all_files_have_signature()`.
+    ///
+    /// The result is a list of (package, version, signed) tuples,
+    /// sorted by package and version.
+    pub fn signing_report(&self, client: &BintrayClient)
+        -> Result<Vec<(String, String, bool)>, BintrayError>
+    {
+        let repository = client.get_repository(&self.owner, &self.repository)?;
+        let has_signed_metadata = matches!(
+            repository.type_,
+            RepositoryType::Debian | RepositoryType::Deb | RepositoryType::Rpm);
+        let mut existing_signatures = HashMap::new();
+
+        let mut report = vec![];
+        for package_name in self.list_packages(client)? {
+            let mut package = Package::new(
+                &self.owner, &self.repository, &package_name);
+            package.get(false, client)?;
+
+            let files = package.list_files(None, false, client)?;
+
+            for version in package.get_versions(None) {
+                let version_files: Vec<Content> = files.iter()
+                    .filter(|file| file.version == version.version)
+                    .cloned()
+                    .collect();
+                let signed = if has_signed_metadata {
+                    self.metadata_signed(&repository,
+                                         &version_files,
+                                         &mut existing_signatures,
+                                         client)?
+                } else {
+                    content::all_files_have_signature(&version_files)
+                };
+
+                report.push((package_name.clone(), version.version, signed));
+            }
+        }
+
+        report.sort();
+        info!("SigningReport({}): {:?}", self, report);
+        Ok(report)
+    }
+
+    /// Tells if the repository metadata indexing `files` is signed.
+    ///
+    /// The existence of each signature file is cached in
+    /// `existing_signatures`, because many versions share the same
+    /// metadata.
+    fn metadata_signed(&self,
+                       repository: &Repository,
+                       files: &[Content],
+                       existing_signatures: &mut HashMap<Url, bool>,
+                       client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        let mut index_urls = HashSet::new();
+        for file in files {
+            let content = Content::with_repository(
+                repository, &file.package, &file.version, &file.path);
+            let no_values: &[&str] = &[];
+            index_urls.extend(content.index_urls(
+                    no_values, no_values, no_values, client)?);
+        }
+
+        let mut signed = false;
+        for index_url in index_urls {
+            let signature_names: &[&str] =
+                match index_url.path_segments().and_then(|mut s| s.next_back()) {
+                    Some("Release") => &["InRelease", "Release.gpg"],
+                    Some("repomd.xml") => &["repomd.xml.asc"],
+                    _ => continue,
+                };
+
+            let mut found = false;
+            for signature_name in signature_names {
+                let mut url = index_url.clone();
+                url.path_segments_mut().unwrap()
+                    .pop()
+                    .push(signature_name);
+
+                let exists = match existing_signatures.get(&url) {
+                    Some(&exists) => exists,
+                    None => self.file_exists(&url, client)?,
+                };
+                existing_signatures.insert(url, exists);
+                if exists {
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                return Ok(false);
+            }
+            signed = true;
+        }
+
+        Ok(signed)
+    }
+
+    fn file_exists(&self, url: &Url, client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        let resp = client.head(url.clone())
+            .send()?;
+
+        let body = String::new();
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("FileExists({}): {} exists", self, url);
+                Ok(true)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                info!("FileExists({}): {} not found", self, url);
+                Ok(false)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "FileExists", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "FileExists",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Returns the ASCII-armored GPG public key used to sign this
+    /// repository's metadata and files, i.e. the owner's key.
+    pub fn gpg_public_key(&self, client: &BintrayClient)
+        -> Result<String, BintrayError>
+    {
+        let mut subject = Subject::new(&self.owner);
+        let kind = subject.kind(client)?;
+
+        let mut url = client.get_base_url();
+        url.path_segments_mut().unwrap()
+            .extend(&[kind.endpoint(), &self.owner,
+                    "keys", "gpg", "public.key"]);
+
+        let mut resp = client.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("GetGpgPublicKey({}): Ok", self);
+                Ok(body)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "GetGpgPublicKey",
+                    io::ErrorKind::NotFound,
+                    "GPG public key not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetGpgPublicKey", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "GetGpgPublicKey",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Writes the GPG public key returned by `gpg_public_key()` to
+    /// `path`, e.g. in `/etc/apt/trusted.gpg.d`, and returns the number
+    /// of bytes written.
+    ///
+    /// The key is checked to be an ASCII-armored PGP public key block
+    /// first: `path` is left untouched otherwise.
+    pub fn install_gpg_key(&self, path: &Path, client: &BintrayClient)
+        -> Result<usize, BintrayError>
+    {
+        let key = self.gpg_public_key(client)?;
+
+        let trimmed = key.trim();
+        if !trimmed.starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----") ||
+            !trimmed.ends_with("-----END PGP PUBLIC KEY BLOCK-----") {
+            error!("InstallGpgKey({}): Not a PGP public key block", self);
+            let error = io::Error::new(
+                io::ErrorKind::InvalidData, format!(
+                    "Bintray::InstallGpgKey({}): Not a PGP public key block",
+                    self));
+            return Err(BintrayError::from(error));
+        }
+
+        fs::write(path, &key)?;
+
+        info!("InstallGpgKey({}): Key written to {}", self, path.display());
+        Ok(key.len())
+    }
+
+    /// Returns the files, across all packages of this repository, which
+    /// were uploaded more than `older_than` ago but never published.
+    ///
+    /// Bintray has no API to list incomplete or failed uploads: files
+    /// left unpublished for a long time are the best approximation of
+    /// what a crashed upload job leaves behind. They can then be removed
+    /// with `Content::remove()`. Files whose creation date is unknown
+    /// or invalid are never considered stale.
+    pub fn stale_unpublished_files(&self,
+                                   older_than: Duration,
+                                   client: &BintrayClient)
+        -> Result<Vec<Content>, BintrayError>
+    {
+        let limit = SystemTime::now().checked_sub(older_than);
+
+        let mut stale = vec![];
+        for package_name in self.list_packages(client)? {
+            let package = Package::new(
+                &self.owner, &self.repository, &package_name);
+            let published = package.list_files(None, false, client)?;
+            let all = package.list_files(None, true, client)?;
+
+            stale.extend(all.into_iter().filter(|file| {
+                let is_published = published.iter().any(|p| {
+                    p.version == file.version && p.path == file.path
+                });
+                let created = file.created.as_ref()
+                    .and_then(|created| utils::parse_rfc3339(created));
+                let is_old = match (created, limit) {
+                    (Some(created), Some(limit)) => created < limit,
+                    _                            => false,
+                };
+                !is_published && is_old
+            }));
+        }
+
+        info!("StaleUnpublishedFiles({}): {} file(s)", self, stale.len());
+        Ok(stale)
+    }
+
+    /// Compares the published files of this repository with a manifest.
+    ///
+    /// The manifest lists the expected files, as paths relative to the
+    /// repository root, with their SHA-256 checksum. The files of every
+    /// package are listed, then the checksum of each file of the
+    /// manifest is queried with `Content::get_remote_checksum()`.
+    ///
+    /// All paths in the returned report are sorted.
+    pub fn verify_manifest(&self,
+                           manifest: &[(PathBuf, Vec<u8>)],
+                           client: &BintrayClient)
+        -> Result<VerifyReport, BintrayError>
+    {
+        let mut remote_files = vec![];
+        for package_name in self.list_packages(client)? {
+            let package = Package::new(
+                &self.owner, &self.repository, &package_name);
+            remote_files.extend(package.list_files(None, false, client)?);
+        }
+
+        let mut report = VerifyReport::default();
+        let mut expected_paths = HashSet::new();
+        for (path, sha256) in manifest {
+            let path = content::clean_path(path);
+            expected_paths.insert(path.clone());
+
+            let file = match remote_files.iter().find(|f| f.path == path) {
+                Some(file) => file,
+                None => {
+                    report.missing.push(path);
+                    continue;
+                }
+            };
+
+            let expected: String = sha256.iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            match file.get_remote_checksum(client)? {
+                Some(ref remote) if remote.to_lowercase() == expected => { }
+                _ => report.mismatched.push(path),
+            }
+        }
+
+        report.extra = remote_files.into_iter()
+            .map(|file| file.path)
+            .filter(|path| !expected_paths.contains(path))
+            .collect();
+
+        report.missing.sort();
+        report.extra.sort();
+        report.mismatched.sort();
+
+        info!("VerifyManifest({}): {:?}", self, report);
+        Ok(report)
+    }
+}
+
+/// Result of `Repository::verify_manifest()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Files of the manifest which are not published.
+    pub missing: Vec<PathBuf>,
+    /// Published files which are not in the manifest.
+    pub extra: Vec<PathBuf>,
+    /// Files whose remote SHA-256 checksum differs from the manifest.
+    pub mismatched: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    /// Returns true if the published files match the manifest exactly.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() &&
+            self.extra.is_empty() &&
+            self.mismatched.is_empty()
+    }
+}
+
+impl RepositoryType {
+    /// Returns the path of a Maven artifact file in the standard Maven
+    /// layout, e.g. `com/example/my-lib/1.0.0/my-lib-1.0.0.jar` for
+    /// the group `com.example`.
+    pub fn maven_path(group: &str,
+                      artifact: &str,
+                      version: &str,
+                      filename: &str) -> PathBuf
+    {
+        let mut path: PathBuf = group.split('.').collect();
+        path.push(artifact);
+        path.push(version);
+        path.push(Path::new(filename));
+        path
+    }
+
+    /// Returns the path segments, relative to the API base URL, where a
+    /// file is uploaded in a repository of this type, e.g.
+    /// `["content", "my-company", "my-repo", "my-package", "1.0.0",
+    /// "file.txt"]`.
+    ///
+    /// Maven repositories use their own endpoint, where the version is
+    /// part of the file path, so `version` is ignored. The other
+    /// arguments are one segment each, even if they contain a `/`;
+    /// `path` is split into its components. Segments are not
+    /// percent-encoded and upload options are not included.
+    pub fn content_upload_segments<T: AsRef<Path>>(&self,
+                                                   subject: &str,
+                                                   repository: &str,
+                                                   package: &str,
+                                                   version: &str,
+                                                   path: T) -> Vec<String>
+    {
+        let mut segments = match *self {
+            RepositoryType::Maven => {
+                vec!["maven", subject, repository, package]
+            }
+            _ => vec!["content", subject, repository, package, version],
+        }.into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+        segments.extend(content::clean_path(path).iter()
+                        .map(|v| v.to_string_lossy().into_owned()));
+        segments
+    }
+
+    /// Same as `content_upload_segments()`, with the segments separated
+    /// by `/`, e.g. `content/my-company/my-repo/my-package/1.0.0/file.txt`.
+    pub fn content_upload_path<T: AsRef<Path>>(&self,
+                                               subject: &str,
+                                               repository: &str,
+                                               package: &str,
+                                               version: &str,
+                                               path: T) -> String
+    {
+        self.content_upload_segments(
+            subject, repository, package, version, path).join("/")
+    }
 }
 
 impl fmt::Display for Repository {
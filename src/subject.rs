@@ -0,0 +1,127 @@
+//! Bintray subject
+//!
+//! A subject is the user or organization owning repositories.
+//!
+//! The following operations are currently supported on a Bintray
+//! subject:
+//! * querying whether it is a user or an organization.
+
+use hyper::status::StatusCode;
+use std::fmt;
+use std::io::{self, Read};
+
+use client::{BintrayClient, BintrayError};
+use utils;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subject {
+    pub name: String,
+    /// Whether the subject is a user or an organization, once queried
+    /// with `kind()`.
+    pub kind: Option<SubjectKind>,
+}
+
+/// Kinds of subjects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubjectKind {
+    User,
+    Organization,
+}
+
+impl Subject {
+    pub fn new(name: &str) -> Subject {
+        Subject {
+            name: String::from(name),
+            kind: None,
+        }
+    }
+
+    /// Returns whether the subject is a user or an organization.
+    ///
+    /// Some endpoints differ between users and organizations. Bintray
+    /// is queried the first time only: the result is cached in `kind`.
+    pub fn kind(&mut self, client: &BintrayClient)
+        -> Result<SubjectKind, BintrayError>
+    {
+        if let Some(kind) = self.kind {
+            return Ok(kind);
+        }
+
+        for &kind in &[SubjectKind::User, SubjectKind::Organization] {
+            if self.probe(kind, client)? {
+                info!("GetSubjectKind({}): {}", self, kind);
+                self.kind = Some(kind);
+                return Ok(kind);
+            }
+        }
+
+        error!("GetSubjectKind({}): Neither a user nor an organization",
+               self);
+        let error = io::Error::new(
+            io::ErrorKind::NotFound, format!(
+                "Bintray::GetSubjectKind({}): Subject not found", self));
+        Err(BintrayError::from(error))
+    }
+
+    fn probe(&self, kind: SubjectKind, client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        let mut url = client.get_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.push(kind.endpoint());
+            path.push(&self.name);
+        }
+
+        let mut resp = client.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                Ok(true)
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                Ok(false)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetSubjectKind", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "GetSubjectKind",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+}
+
+impl SubjectKind {
+    /// Returns the first path segment of the API endpoints specific to
+    /// this kind of subject.
+    pub fn endpoint(&self) -> &'static str {
+        match *self {
+            SubjectKind::User         => "users",
+            SubjectKind::Organization => "orgs",
+        }
+    }
+}
+
+impl fmt::Display for Subject {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl fmt::Display for SubjectKind {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match *self {
+            SubjectKind::User         => "user",
+            SubjectKind::Organization => "organization",
+        };
+        write!(f, "{}", kind)
+    }
+}
@@ -1,5 +1,17 @@
+use hyper::header::Headers;
+use hyper::status::StatusCode;
+use hyper::Url;
 use serde_json;
 use serde_json::value::Value;
+use std::{cmp, fmt, io, thread};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use client::BintrayError;
+
+/// Delay between two attempts of `wait_for_condition()`.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Deserialize)]
 struct BintrayMessage {
@@ -23,6 +35,252 @@ pub fn prettify_json(input: &str) -> String {
     }
 }
 
+/// Returns the value of a header as a string, if it is present and
+/// valid UTF-8.
+pub fn get_raw_header(headers: &Headers, name: &str) -> Option<String> {
+    headers.get_raw(name)
+        .and_then(|values| values.first())
+        .and_then(|value| String::from_utf8(value.clone()).ok())
+        .map(|value| String::from(value.trim()))
+}
+
+/// Maximum length of a subject, repository, package or version name.
+const MAX_NAME_LEN: usize = 255;
+
+/// Checks a subject, repository, package or version name against
+/// Bintray naming rules.
+///
+/// Only obviously invalid names are rejected: an empty or too long
+/// name, `.` or `..`, or a name with characters other than ASCII
+/// letters, digits and `-_.:+~@`. Bintray may still refuse a name
+/// accepted here.
+pub fn validate_name(name: &str) -> Result<(), BintrayError> {
+    let reason = if name.is_empty() {
+        Some(String::from("Name is empty"))
+    } else if name.len() > MAX_NAME_LEN {
+        Some(format!("Name is longer than {} characters", MAX_NAME_LEN))
+    } else if name == "." || name == ".." {
+        Some(String::from("Name is a reserved path component"))
+    } else {
+        name.chars()
+            .find(|&c| !c.is_ascii_alphanumeric() && !"-_.:+~@".contains(c))
+            .map(|c| format!("Character {:?} is not allowed", c))
+    };
+
+    match reason {
+        Some(reason) => {
+            Err(BintrayError::InvalidName {
+                name: String::from(name),
+                reason,
+            })
+        }
+        None => Ok(()),
+    }
+}
+
+/// Returns true if `text` matches the shell-like `pattern`.
+///
+/// `*` matches any sequence of characters and `?` matches exactly one
+/// character. There is no escaping.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and of the text
+    // character it was matched against, to backtrack.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Splits a comma-separated list (e.g. `label-1, label-2`), trimming
+/// whitespaces and dropping empty elements.
+pub fn split_csv(input: &str) -> Vec<String> {
+    input.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Returns a link to a tag in the web interface of a VCS hosting
+/// service, from the repository URL (e.g.
+/// `https://github.com/my-company/my-project.git` or
+/// `git@github.com:my-company/my-project.git`).
+///
+/// GitHub, GitLab and Bitbucket are supported. `None` is returned for
+/// other hosts or unparsable URLs.
+pub fn vcs_tag_url(vcs_url: &str, tag: &str) -> Option<Url> {
+    let vcs_url = vcs_url.trim();
+
+    // Turn scp-like Git URLs into regular ones.
+    let url = match Url::parse(vcs_url) {
+        Ok(url) => url,
+        Err(_) => {
+            let (user_host, path) = vcs_url.split_at(vcs_url.find(':')?);
+            Url::parse(&format!("ssh://{}/{}", user_host, &path[1..])).ok()?
+        }
+    };
+
+    let host = url.host_str()?.trim_start_matches("www.").to_lowercase();
+    let project: Vec<&str> = url.path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if project.len() < 2 {
+        return None;
+    }
+    let last = project.len() - 1;
+    let name = project[last].trim_end_matches(".git");
+
+    let mut tag_url = Url::parse(&format!("https://{}/", host)).ok()?;
+    {
+        let mut path = tag_url.path_segments_mut().ok()?;
+        path.pop_if_empty();
+        path.extend(&project[..last]);
+        path.push(name);
+        match host.as_str() {
+            "github.com"    => { path.extend(&["tree", tag]); }
+            "gitlab.com"    => { path.extend(&["-", "tags", tag]); }
+            "bitbucket.org" => { path.extend(&["src", tag]); }
+            _               => return None,
+        }
+    }
+    Some(tag_url)
+}
+
+/// Formats a time as an ISO 8601 UTC timestamp, the way Bintray reports
+/// dates (e.g. `2017-03-01T12:34:56.789Z`).
+///
+/// Timestamps received from Bintray may use other RFC 3339 forms: parse
+/// them with `parse_rfc3339()` rather than comparing strings.
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0));
+    let secs = since_epoch.as_secs();
+    let secs_of_day = secs % 86400;
+
+    // Converts the number of days since 1970-01-01 to a civil date.
+    // See http://howardhinnant.github.io/date_algorithms.html
+    let z = secs / 86400 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year, month, day,
+            secs_of_day / 3600, secs_of_day % 3600 / 60, secs_of_day % 60,
+            since_epoch.subsec_millis())
+}
+
+/// Parses an RFC 3339 timestamp, e.g. `2017-03-01T12:34:56.789Z` or
+/// `2017-03-01T13:34:56+01:00`, whatever its precision and offset.
+///
+/// Returns `None` if `timestamp` is not a valid RFC 3339 timestamp.
+pub fn parse_rfc3339(timestamp: &str) -> Option<SystemTime> {
+    fn number(value: &str) -> Option<i64> {
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        value.parse().ok()
+    }
+
+    let timestamp = timestamp.trim();
+    if timestamp.len() < 20 || !timestamp.is_ascii() {
+        return None;
+    }
+    let (date, rest) = timestamp.split_at(10);
+    let date: Vec<&str> = date.split('-').collect();
+    if date.len() != 3 || date[0].len() != 4 ||
+        date[1].len() != 2 || date[2].len() != 2 {
+        return None;
+    }
+    let (year, month, day) =
+        (number(date[0])?, number(date[1])?, number(date[2])?);
+
+    if !rest.starts_with(['T', 't', ' ']) {
+        return None;
+    }
+    let time = &rest[1..];
+    if time.len() < 9 || &time[2..3] != ":" || &time[5..6] != ":" {
+        return None;
+    }
+    let (hour, minute, second) =
+        (number(&time[0..2])?, number(&time[3..5])?, number(&time[6..8])?);
+
+    let mut rest = &time[8..];
+    let mut nanos = 0;
+    if rest.starts_with('.') {
+        let digits = rest[1..].bytes()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            return None;
+        }
+        let fraction = &rest[1..(1 + digits)];
+        let padded = format!("{:0<9}", &fraction[..cmp::min(digits, 9)]);
+        nanos = number(&padded)? as u32;
+        rest = &rest[(1 + digits)..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && &rest[3..4] == ":" => {
+            let offset = number(&rest[1..3])? * 3600 +
+                number(&rest[4..6])? * 60;
+            match &rest[0..1] {
+                "+" => offset,
+                "-" => -offset,
+                _   => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) ||
+        hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Converts a civil date to the number of days since 1970-01-01.
+    // See http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::new(secs as u64, nanos))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::new(-secs as u64, 0))
+            .and_then(|time| time.checked_add(Duration::new(0, nanos)))
+    }
+}
+
 #[macro_export]
 macro_rules! format_status_line {
     ($resp:expr) => ({
@@ -41,6 +299,23 @@ pub fn get_bintray_message(body: String, default_message: &str) -> String {
     }
 }
 
+/// Same as `get_bintray_message()`, but if Bintray returned an empty
+/// message, the reason phrase of `status` (e.g. "Forbidden") is used
+/// instead.
+pub fn get_bintray_error_message(body: String,
+                                 status: StatusCode,
+                                 default_message: &str) -> String {
+    match serde_json::from_str::<BintrayMessage>(&body) {
+        Ok(ref message) if message.message.trim().is_empty() => {
+            status.canonical_reason()
+                .unwrap_or(default_message)
+                .to_string()
+        }
+        Ok(message) => message.message,
+        Err(_)      => default_message.to_string(),
+    }
+}
+
 pub fn get_bintray_warning(body: &str) -> Option<String> {
     match serde_json::from_str::<BintrayWarning>(body) {
         Ok(message) => Some(message.warn),
@@ -80,7 +355,7 @@ macro_rules! report_bintray_error {
             $errorkind, format!(
                 "Bintray::{}({}): {} ({})",
                 $function, $object,
-                utils::get_bintray_message($body, $msg),
+                utils::get_bintray_error_message($body, $resp.status, $msg),
                 status_line));
         Err(BintrayError::from(error))
     });
@@ -101,8 +376,167 @@ macro_rules! report_bintray_error {
             $errorkind, format!(
                 "Bintray::{}({}): {} ({})",
                 $function, $object,
-                utils::get_bintray_message($body, $msg),
+                utils::get_bintray_error_message($body, $resp.status, $msg),
                 status_line));
         Err(BintrayError::from(error))
     })
 }
+
+/// Reports a "401 Unauthorized" response.
+///
+/// The error is `BintrayError::InvalidCredentials` if the client sent
+/// credentials, `BintrayError::Unauthorized` otherwise.
+#[macro_export]
+macro_rules! report_bintray_unauthorized {
+    ($object:expr, $resp:expr, $body:expr, $function:expr, $client:expr) => ({
+        let status_line = format_status_line!($resp);
+        error!(
+            "{}({}): {}\n{}",
+            $function, $object, status_line, utils::prettify_json(&$body));
+
+        let object = $object.to_string();
+        if $client.is_authenticated() {
+            Err(BintrayError::InvalidCredentials {
+                object,
+                message: utils::get_bintray_error_message(
+                    $body, $resp.status, "Refused authentication"),
+            })
+        } else {
+            Err(BintrayError::Unauthorized {
+                object,
+                message: utils::get_bintray_error_message(
+                    $body, $resp.status, "Missing authentication"),
+            })
+        }
+    })
+}
+
+/// Reports a "403 Forbidden" response as
+/// `BintrayError::PermissionDenied`.
+#[macro_export]
+macro_rules! report_bintray_forbidden {
+    ($object:expr, $resp:expr, $body:expr, $function:expr) => ({
+        let status_line = format_status_line!($resp);
+        error!(
+            "{}({}): {}\n{}",
+            $function, $object, status_line, utils::prettify_json(&$body));
+
+        Err(BintrayError::PermissionDenied {
+            object: $object.to_string(),
+            message: utils::get_bintray_error_message(
+                $body, $resp.status, "Requires admin privileges"),
+        })
+    })
+}
+
+/// A source of time for `wait_for_condition_with_clock()`.
+///
+/// `SystemClock` is the real one; `mock::MockClock` lets tests check
+/// timeouts without actually sleeping.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The clock used by `wait_for_condition()`, backed by `Instant::now()`
+/// and `thread::sleep()`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// Calls `condition` repeatedly until it returns true or `timeout`
+/// elapses.
+///
+/// An error returned by `condition` is returned immediately. If the
+/// timeout is reached, an `io::ErrorKind::TimedOut` error is returned;
+/// `function` and `object` are used to format its message the same way
+/// other Bintray errors are.
+pub fn wait_for_condition<T, F>(object: &T,
+                                function: &str,
+                                timeout: Duration,
+                                condition: F)
+    -> Result<(), BintrayError>
+    where T: fmt::Display,
+          F: FnMut() -> Result<bool, BintrayError>
+{
+    wait_for_condition_with_clock(
+        &SystemClock, object, function, timeout, condition)
+}
+
+/// Same as `wait_for_condition()`, but measures time and sleeps using
+/// `clock`.
+pub fn wait_for_condition_with_clock<C, T, F>(clock: &C,
+                                              object: &T,
+                                              function: &str,
+                                              timeout: Duration,
+                                              mut condition: F)
+    -> Result<(), BintrayError>
+    where C: Clock,
+          T: fmt::Display,
+          F: FnMut() -> Result<bool, BintrayError>
+{
+    let start = clock.now();
+    loop {
+        if condition()? {
+            return Ok(());
+        }
+
+        let elapsed = clock.now().duration_since(start);
+        if elapsed >= timeout {
+            let error = io::Error::new(
+                io::ErrorKind::TimedOut, format!(
+                    "Bintray::{}({}): Timed out after {} seconds",
+                    function, object, timeout.as_secs()));
+            return Err(BintrayError::from(error));
+        }
+
+        debug!("{}({}): Condition not met yet, retrying", function, object);
+        clock.sleep(cmp::min(POLL_INTERVAL, timeout - elapsed));
+    }
+}
+
+/// Calls `function` on each item of `items` and returns the results in
+/// the same order.
+///
+/// Items are handed out to a fixed pool of `concurrency` threads, so at
+/// most `concurrency` calls run at the same time.
+pub fn parallel_map<T, R, F>(items: &[T], concurrency: usize, function: F)
+    -> Vec<R>
+    where T: Sync,
+          R: Send,
+          F: Fn(&T) -> R + Sync
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..cmp::max(concurrency, 1) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, AtomicOrdering::SeqCst);
+                    if index >= items.len() {
+                        break;
+                    }
+
+                    let result = function(&items[index]);
+                    results.lock().unwrap().push((index, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(index, _)| index);
+    results.into_iter()
+        .map(|(_, result)| result)
+        .collect()
+}
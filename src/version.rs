@@ -1,12 +1,20 @@
+use hyper::Url;
 use hyper::status::StatusCode;
 use serde_json;
+use version_compare::{self, VersionCompare};
 use std::collections::HashMap;
 use std::fmt;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-use client::{BintrayClient, BintrayError};
+use client::{BintrayClient, BintrayError, OperationStatus};
 use package::Package;
-use content::Content;
+use content::{self, ChecksumAlgorithm, Content, CopyReport,
+              UploadOptions};
+use repository::RepositoryType;
 use utils;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -50,6 +58,65 @@ pub struct Version {
     pub attributes: Option<HashMap<String, String>>,
 }
 
+/// Name of the version attribute holding the date after which the
+/// version may be deleted.
+pub const TTL_ATTRIBUTE: &str = "delete_after";
+
+/// Result of `Version::publish_content()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublishResult {
+    /// The number of files published or discarded.
+    pub files: usize,
+    /// Warnings reported by Bintray. Publishing no files at all is
+    /// reported as a warning too.
+    pub warnings: Vec<String>,
+}
+
+/// Options of `Version::upload_and_publish()`.
+#[derive(Clone, Debug, Default)]
+pub struct PublishOptions {
+    /// Options of the upload. `publish` is ignored: the files are
+    /// published afterwards with `publish_content()`.
+    pub upload: UploadOptions,
+    /// Type of the repository, to use the right upload URL (see
+    /// `Content::with_repository()`).
+    pub repository_type: Option<RepositoryType>,
+    /// Checksums to compute and send along with the upload (see
+    /// `Content::set_checksum_header_algorithm()`).
+    pub checksum_header_algorithm: Option<ChecksumAlgorithm>,
+    /// Passed to `publish_content()`.
+    pub wait_for_publish: Option<i32>,
+    /// If set, waits until the file can be downloaded, at most this
+    /// long, after publishing.
+    pub availability_timeout: Option<Duration>,
+}
+
+/// Result of `Version::summary()`.
+///
+/// It is displayed as one `key: value` line per field, then one
+/// `file: <sha1> <path>` line per file, so it can be grepped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReleaseSummary {
+    /// The version, as `owner/repository/package/version`.
+    pub version: String,
+    pub published: bool,
+    pub file_count: usize,
+    pub total_size: u64,
+    /// Whether every file has a per-file `.asc` signature, see
+    /// `content::all_files_have_signature()`.
+    pub signed: bool,
+    /// The SHA-1 checksum of each file, sorted by path.
+    pub checksums: Vec<(PathBuf, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Attribute {
+    name: String,
+    values: Vec<String>,
+    #[serde(rename = "type")]
+    type_: String,
+}
+
 impl Version {
     pub fn new(owner: &str, repository: &str, package: &str,
                version: &str) -> Version
@@ -78,6 +145,31 @@ impl Version {
         }
     }
 
+    /// Same as `new()`, but checks names with `utils::validate_name()`
+    /// first, so obviously invalid names are rejected before reaching
+    /// Bintray.
+    pub fn try_new(owner: &str, repository: &str, package: &str,
+                   version: &str) -> Result<Version, BintrayError>
+    {
+        utils::validate_name(owner)?;
+        utils::validate_name(repository)?;
+        utils::validate_name(package)?;
+        utils::validate_name(version)?;
+
+        Ok(Version::new(owner, repository, package, version))
+    }
+
+    /// Sets the release date submitted by `create()` and `update()`.
+    pub fn set_released(mut self, released: SystemTime) -> Version {
+        self.released = Some(utils::format_rfc3339(released));
+        self
+    }
+
+    /// Same as `set_released()` with the current time.
+    pub fn released_now(self) -> Version {
+        self.set_released(SystemTime::now())
+    }
+
     pub fn get(&mut self, get_attribute_values: bool, client: &BintrayClient)
         -> Result<(), BintrayError>
     {
@@ -110,11 +202,12 @@ impl Version {
                 self.desc = queried.desc;
                 self.labels = queried.labels
                     .map(|mut v| { v.sort(); v });
+                self.published = queried.published;
                 self.created = queried.created;
                 self.updated = queried.updated;
                 self.released = queried.released
                     .and_then(|s| {
-                        if s.is_empty() { None } else {  Some(s) }
+                        if s.is_empty() { None } else { Some(s) }
                     });
                 self.ordinal = queried.ordinal;
 
@@ -139,10 +232,8 @@ impl Version {
                     "Package version not found", true)
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "GetVersion",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetVersion", client)
             }
             _ => {
                 report_bintray_error!(
@@ -226,13 +317,29 @@ impl Version {
                 self.updated = created.updated;
                 // TODO: Assert that created == package. */
 
+                // Bintray may normalize the release date (e.g. its
+                // timezone): keep the stored value.
+                let released = created.released
+                    .and_then(|s| {
+                        if s.is_empty() { None } else { Some(s) }
+                    });
+                if let Some(released) = released {
+                    match self.released {
+                        Some(ref submitted) if *submitted != released => {
+                            info!("CreateVersion({}): Release date {} \
+                                   stored as {}",
+                                  self, submitted, released);
+                        }
+                        _ => { }
+                    }
+                    self.released = Some(released);
+                }
+
                 Ok(())
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "CreateVersion",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "CreateVersion", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -305,10 +412,8 @@ impl Version {
                 Ok(())
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "UpdateVersion",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "UpdateVersion", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -353,31 +458,244 @@ impl Version {
                     self, resp, body, "DeleteVersion")
             }
             _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "DeleteVersion", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
                     self, resp, body, "DeleteVersion",
                     io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                    "Requires admin privileges")
             }
-            _ if resp.status == StatusCode::Forbidden => {
+            _ => {
                 report_bintray_error!(
                     self, resp, body, "DeleteVersion",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Signs the files of this version with the owner's GPG key.
+    ///
+    /// The GPG passphrase configured on the `BintrayClient` is sent
+    /// along.
+    pub fn sign(&self, client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let mut url = client.get_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.extend(&["gpg",
+                        &self.owner,
+                        &self.repository,
+                        &self.package,
+                        "versions",
+                        &self.version]);
+        }
+
+        let mut resp = client.post(url)
+            .add_client_gpg_passphrase()
+            .send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("SignVersion({}): {}", self, body);
+
+                report_bintray_warning!(
+                    self, resp, body, "SignVersion")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "SignVersion", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
+                report_bintray_error!(
+                    self, resp, body, "SignVersion",
                     io::ErrorKind::PermissionDenied,
                     "Requires admin privileges")
             }
             _ => {
                 report_bintray_error!(
-                    self, resp, body, "DeleteVersion",
+                    self, resp, body, "SignVersion",
                     io::ErrorKind::Other,
                     "Unrecognized error")
             }
         }
     }
 
+    /// Sets the values of an attribute of this version.
+    ///
+    /// `type_` is the Bintray attribute type (`string`, `date`,
+    /// `number`, `boolean`, etc.).
+    pub fn set_attribute<T: Borrow<str>>(&self,
+                                         name: &str,
+                                         type_: &str,
+                                         values: &[T],
+                                         client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        let url = self.get_attributes_url(client);
+
+        let args = vec![Attribute {
+            name: String::from(name),
+            values: values.iter().map(|s| String::from(s.borrow())).collect(),
+            type_: String::from(type_),
+        }];
+
+        let json = serde_json::to_string_pretty(&args)?;
+        info!(
+            "SetVersionAttribute({}): Submitting the following properties:\n{}",
+            self, json);
+
+        let mut resp = client.post(url)
+            .body(&json)
+            .send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("SetVersionAttribute({}): {}", self, body);
+                Ok(())
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "SetVersionAttribute",
+                    io::ErrorKind::NotFound,
+                    "Package version not found")
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "SetVersionAttribute", client)
+            }
+            _ if resp.status == StatusCode::Forbidden => {
+                report_bintray_error!(
+                    self, resp, body, "SetVersionAttribute",
+                    io::ErrorKind::PermissionDenied,
+                    "Requires admin privileges")
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "SetVersionAttribute",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    /// Returns the values of an attribute of this version, or `None` if
+    /// the attribute isn't set.
+    pub fn get_attribute(&self, name: &str, client: &BintrayClient)
+        -> Result<Option<Vec<String>>, BintrayError>
+    {
+        let mut url = self.get_attributes_url(client);
+        url.query_pairs_mut().append_pair("names", name);
+
+        let mut resp = client.get(url).send()?;
+
+        let mut body = String::new();
+        resp.read_to_string(&mut body)?;
+
+        match resp {
+            _ if resp.status == StatusCode::Ok => {
+                info!("GetVersionAttribute({}): {}", self, body);
+
+                let attributes: Vec<Attribute> = serde_json::from_str(&body)?;
+                Ok(attributes.into_iter()
+                   .find(|attribute| attribute.name == name)
+                   .map(|attribute| attribute.values))
+            }
+            _ if resp.status == StatusCode::NotFound => {
+                report_bintray_error!(
+                    self, resp, body, "GetVersionAttribute",
+                    io::ErrorKind::NotFound,
+                    "Package version not found", true)
+            }
+            _ if resp.status == StatusCode::Unauthorized => {
+                report_bintray_unauthorized!(
+                    self, resp, body, "GetVersionAttribute", client)
+            }
+            _ => {
+                report_bintray_error!(
+                    self, resp, body, "GetVersionAttribute",
+                    io::ErrorKind::Other,
+                    "Unrecognized error")
+            }
+        }
+    }
+
+    fn get_attributes_url(&self, client: &BintrayClient) -> Url {
+        let mut url = client.get_base_url();
+        {
+            let mut path = url.path_segments_mut().unwrap();
+            path.extend(&["packages",
+                        &self.owner,
+                        &self.repository,
+                        &self.package,
+                        "versions",
+                        &self.version,
+                        "attributes"]);
+        }
+        url
+    }
+
+    /// Records the date after which this version may be deleted, in the
+    /// `delete_after` attribute.
+    ///
+    /// Bintray doesn't delete the version by itself: the TTL is only
+    /// enforced by a cleanup job using `Repository::expired_versions()`.
+    pub fn set_ttl(&self, delete_after: SystemTime, client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        let delete_after = utils::format_rfc3339(delete_after);
+        self.set_attribute(TTL_ATTRIBUTE, "date", &[delete_after], client)
+    }
+
+    /// Returns true if the date recorded by `set_ttl()` is in the past.
+    ///
+    /// A version without a TTL never expires. An
+    /// `io::ErrorKind::InvalidData` error is returned if the recorded
+    /// date is not a valid RFC 3339 timestamp.
+    pub fn is_expired(&self, client: &BintrayClient)
+        -> Result<bool, BintrayError>
+    {
+        let delete_after = self.get_attribute(TTL_ATTRIBUTE, client)?;
+
+        match delete_after.and_then(|values| values.into_iter().next()) {
+            Some(delete_after) => {
+                match utils::parse_rfc3339(&delete_after) {
+                    Some(delete_after) => Ok(delete_after < SystemTime::now()),
+                    None => {
+                        error!("IsExpired({}): Invalid TTL date \"{}\"",
+                               self, delete_after);
+                        let error = io::Error::new(
+                            io::ErrorKind::InvalidData, format!(
+                                "Bintray::IsExpired({}): Invalid TTL date \
+                                 \"{}\"", self, delete_after));
+                        Err(BintrayError::from(error))
+                    }
+                }
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Publishes (or discards) the unpublished files of this version and
+    /// returns the number of files affected, along with any warning.
+    ///
+    /// If Bintray doesn't complete the operation within
+    /// `wait_for_publish` seconds, it replies with "202 Accepted" and
+    /// `OperationStatus::Pending` is returned.
     pub fn publish_content(&self,
                            wait_for_publish: Option<i32>,
                            discard_unpublished: bool,
                            client: &BintrayClient)
-        -> Result<usize, BintrayError>
+        -> Result<OperationStatus<PublishResult>, BintrayError>
     {
         let mut url = client.get_base_url();
         {
@@ -399,7 +717,8 @@ impl Version {
 
         #[derive(Deserialize)]
         struct PublishContentResp {
-            files: usize
+            files: usize,
+            warn: Option<String>,
         };
 
         let args = PublishContentReq {
@@ -426,13 +745,27 @@ impl Version {
                 let result: PublishContentResp =
                     serde_json::from_str(&body)?;
 
-                Ok(result.files)
+                let mut warnings: Vec<String> =
+                    result.warn.into_iter().collect();
+                if result.files == 0 {
+                    warnings.push(String::from("No files published"));
+                }
+                for warning in &warnings {
+                    warn!("PublishContent({}): {}", self, warning);
+                }
+
+                Ok(OperationStatus::Done(PublishResult {
+                    files: result.files,
+                    warnings,
+                }))
+            }
+            _ if resp.status == StatusCode::Accepted => {
+                info!("PublishContent({}): Pending: {}", self, body);
+                Ok(OperationStatus::Pending)
             }
             _ if resp.status == StatusCode::Unauthorized => {
-                report_bintray_error!(
-                    self, resp, body, "PublishContent",
-                    io::ErrorKind::PermissionDenied,
-                    "Missing or refused authentication")
+                report_bintray_unauthorized!(
+                    self, resp, body, "PublishContent", client)
             }
             _ if resp.status == StatusCode::Forbidden => {
                 report_bintray_error!(
@@ -449,6 +782,54 @@ impl Version {
         }
     }
 
+    /// Uploads `local_filename` to `remote_path`, publishes this
+    /// version's files, then optionally waits for the file to be
+    /// available for download.
+    ///
+    /// This chains `Content::upload_with_options()`,
+    /// `publish_content()` and `Content::wait_for_availability()` with
+    /// the given options.
+    /// Warnings from the upload and the publication are returned.
+    pub fn upload_and_publish<P: AsRef<Path>>(&self,
+                                              local_filename: &PathBuf,
+                                              remote_path: P,
+                                              options: &PublishOptions,
+                                              client: &BintrayClient)
+        -> Result<Vec<String>, BintrayError>
+    {
+        let mut content = Content::new(
+            &self.owner, &self.repository, &self.package, &self.version,
+            remote_path);
+        content.repository_type = options.repository_type.clone();
+        if let Some(algorithm) = options.checksum_header_algorithm {
+            content = content.set_checksum_header_algorithm(algorithm);
+            content.set_checksum_from_file(local_filename)?;
+        }
+
+        let upload_options = options.upload.clone().set_publish(false);
+        let mut warnings: Vec<String> = content.upload_with_options(
+            local_filename, &upload_options, client)?
+            .into_iter()
+            .collect();
+
+        let status = self.publish_content(
+            options.wait_for_publish, false, client)?;
+        match status {
+            OperationStatus::Done(result) => warnings.extend(result.warnings),
+            OperationStatus::Pending => {
+                info!("UploadAndPublish({}): Publication still in progress",
+                      self);
+            }
+        }
+
+        if let Some(timeout) = options.availability_timeout {
+            content.wait_for_availability(timeout, client)?;
+        }
+
+        info!("UploadAndPublish({}): {} published", self, content);
+        Ok(warnings)
+    }
+
     pub fn list_files(&self,
                       include_unpublished: bool,
                       client: &BintrayClient)
@@ -459,6 +840,363 @@ impl Version {
                                    &self.package);
         package.list_files(Some(&self.version), include_unpublished, client)
     }
+
+    /// Copies the files of this version to another package and/or
+    /// version of the same repository.
+    ///
+    /// `dest_path` gives the path of each copy from the path of the
+    /// original file: paths are unique across a repository, so they
+    /// must differ (e.g. by replacing the version in them). The copies
+    /// are not published. Failures don't stop the other copies: they
+    /// are collected in the returned report.
+    pub fn copy_all_to<F>(&self,
+                          dest_package: &str,
+                          dest_version: &str,
+                          dest_path: F,
+                          client: &BintrayClient)
+        -> Result<CopyReport, BintrayError>
+        where F: Fn(&Path) -> PathBuf
+    {
+        let mut report = CopyReport::default();
+        for file in self.list_files(true, client)? {
+            let dest = Content::new(
+                &self.owner, &self.repository, dest_package, dest_version,
+                dest_path(&file.path));
+            match file.copy_to(&dest, false, client) {
+                Ok(_)  => report.copied.push(dest.path),
+                Err(e) => {
+                    warn!("CopyVersionContent({}): Failed to copy {}: {}",
+                          self, file, e);
+                    report.failed.push((file, e));
+                }
+            }
+        }
+
+        info!("CopyVersionContent({}): {} file(s) copied to {}/{}, {} failed",
+              self, report.copied.len(), dest_package, dest_version,
+              report.failed.len());
+        Ok(report)
+    }
+
+    /// Returns the total size, in bytes, of the files of this version,
+    /// published or not.
+    pub fn total_size(&self, client: &BintrayClient)
+        -> Result<u64, BintrayError>
+    {
+        let files = self.list_files(true, client)?;
+        Ok(content::total_size(&files))
+    }
+
+    /// Returns the number of published and unpublished files of this
+    /// version, in that order.
+    ///
+    /// The file listing doesn't tell if a file is published: the files
+    /// are listed twice, without and with unpublished files.
+    pub fn publication_counts(&self, client: &BintrayClient)
+        -> Result<(u64, u64), BintrayError>
+    {
+        let published = self.list_files(false, client)?.len() as u64;
+        let all = self.list_files(true, client)?.len() as u64;
+        let unpublished = all.saturating_sub(published);
+
+        info!("PublicationCounts({}): {} published, {} unpublished",
+              self, published, unpublished);
+        Ok((published, unpublished))
+    }
+
+    /// Shows or hides all published files of this version in the
+    /// public download list.
+    ///
+    /// Bintray has no version-level setting: each file is updated in
+    /// turn. This is useful to hide pre-releases for instance. If the
+    /// client lacks the rights to update a file,
+    /// `BintrayError::PermissionDenied` is returned.
+    pub fn show_in_download_list(&self, show: bool, client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        for file in self.list_files(false, client)? {
+            file.show_in_download_list(show, client)?;
+        }
+
+        info!("ShowVersionInDownloadList({}): {}", self, show);
+        Ok(())
+    }
+
+    /// Tells for each path if a file exists in this version, published
+    /// or not.
+    ///
+    /// The files of the version are listed once, instead of querying
+    /// each file. The result is in the same order as `paths`.
+    pub fn files_exist(&self, paths: &[PathBuf], client: &BintrayClient)
+        -> Result<Vec<(PathBuf, bool)>, BintrayError>
+    {
+        let files = self.list_files(true, client)?;
+
+        let result = paths.iter()
+            .map(|path| {
+                let cleaned_path = content::clean_path(path);
+                let exists = files.iter().any(|file| file.path == cleaned_path);
+                (path.clone(), exists)
+            })
+            .collect();
+        Ok(result)
+    }
+
+    /// Waits until this version holds exactly `expected` files,
+    /// published or not.
+    ///
+    /// This is useful after a multi-file upload to make sure all files
+    /// were registered by Bintray before publishing them. An
+    /// `io::ErrorKind::TimedOut` error is returned if the count doesn't
+    /// match after `timeout`.
+    pub fn wait_for_file_count(&self,
+                               expected: usize,
+                               timeout: Duration,
+                               client: &BintrayClient)
+        -> Result<(), BintrayError>
+    {
+        utils::wait_for_condition(self, "WaitForFileCount", timeout, || {
+            let count = self.list_files(true, client)?.len();
+            info!("WaitForFileCount({}): {} file(s), expecting {}",
+                  self, count, expected);
+            Ok(count == expected)
+        })
+    }
+
+    /// Waits until the given files of this version can be downloaded.
+    ///
+    /// Up to `concurrency` files are waited for in parallel, each one
+    /// with `Content::wait_for_availability()`. Returns, for each path
+    /// and in the same order as `paths`, whether the file became
+    /// available before `timeout`. Errors other than timeouts are
+    /// returned as is.
+    pub fn wait_for_files_available(&self,
+                                    paths: &[PathBuf],
+                                    timeout: Duration,
+                                    concurrency: usize,
+                                    client: &BintrayClient)
+        -> Result<Vec<(PathBuf, bool)>, BintrayError>
+    {
+        utils::parallel_map(paths, concurrency, |path| {
+            let file = Content::new(&self.owner,
+                                    &self.repository,
+                                    &self.package,
+                                    &self.version,
+                                    path);
+            match file.wait_for_availability(timeout, client) {
+                Ok(()) => Ok((path.clone(), true)),
+                Err(BintrayError::Io(ref e))
+                    if e.kind() == io::ErrorKind::TimedOut => {
+                        Ok((path.clone(), false))
+                    }
+                Err(e) => Err(e),
+            }
+        })
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns a summary of this version and its files, published or
+    /// not, e.g. to report the outcome of a release.
+    pub fn summary(&self, client: &BintrayClient)
+        -> Result<ReleaseSummary, BintrayError>
+    {
+        let mut version = self.clone();
+        version.get(false, client)?;
+
+        let files = self.list_files(true, client)?;
+
+        let mut checksums: Vec<(PathBuf, String)> = files.iter()
+            .map(|file| {
+                (file.path.clone(), file.sha1.clone().unwrap_or_default())
+            })
+            .collect();
+        checksums.sort();
+
+        Ok(ReleaseSummary {
+            version: self.to_string(),
+            published: version.published.unwrap_or(false),
+            file_count: files.len(),
+            total_size: content::total_size(&files),
+            signed: content::all_files_have_signature(&files),
+            checksums,
+        })
+    }
+
+    /// Returns a link to the source of this version, from its `vcs_tag`
+    /// and the package's `vcs_url`.
+    ///
+    /// The package is queried to get its `vcs_url`. `None` is returned
+    /// if either is unset or if the VCS host isn't recognized by
+    /// `utils::vcs_tag_url()`.
+    pub fn source_url(&self, client: &BintrayClient)
+        -> Result<Option<Url>, BintrayError>
+    {
+        let vcs_tag = match self.vcs_tag {
+            Some(ref vcs_tag) => vcs_tag,
+            None => return Ok(None),
+        };
+
+        let mut package = Package::new(&self.owner,
+                                       &self.repository,
+                                       &self.package);
+        package.get(false, client)?;
+
+        let source_url = package.vcs_url.as_ref()
+            .and_then(|vcs_url| utils::vcs_tag_url(vcs_url, vcs_tag));
+        info!("GetVersionSourceUrl({}): {:?}", self, source_url);
+        Ok(source_url)
+    }
+
+    /// Uploads a Maven artifact file to this version, in a Maven
+    /// repository.
+    ///
+    /// The remote path follows the standard Maven layout, see
+    /// `RepositoryType::maven_path()`. The file is named after the
+    /// artifact, the version and the optional classifier, with the
+    /// extension of `local_filename` (e.g. `my-lib-1.0.0-sources.jar`).
+    pub fn upload_maven(&self,
+                        group: &str,
+                        artifact: &str,
+                        classifier: Option<&str>,
+                        local_filename: &PathBuf,
+                        publish: bool,
+                        client: &BintrayClient)
+        -> Result<Option<String>, BintrayError>
+    {
+        let mut filename = format!("{}-{}", artifact, self.version);
+        if let Some(classifier) = classifier {
+            filename.push('-');
+            filename.push_str(classifier);
+        }
+        if let Some(extension) = local_filename.extension() {
+            filename.push('.');
+            filename.push_str(&extension.to_string_lossy());
+        }
+
+        let path = RepositoryType::maven_path(
+            group, artifact, &self.version, &filename);
+        let mut file = Content::new(&self.owner,
+                                    &self.repository,
+                                    &self.package,
+                                    &self.version,
+                                    path);
+        file.repository_type = Some(RepositoryType::Maven);
+
+        let options = UploadOptions::new().set_publish(publish);
+        file.upload_with_options(local_filename, &options, client)
+    }
+
+    /// Downloads all published files of this version under `dest_dir`
+    /// and returns their local paths.
+    ///
+    /// The paths of the files relative to the repository root are kept.
+    /// Up to `concurrency` files are downloaded in parallel, see
+    /// `content::download_many()`. The first error is returned, once all
+    /// downloads are finished; a file whose path could point outside of
+    /// `dest_dir` fails with `BintrayError::UnsafePath`.
+    pub fn download_all<P: AsRef<Path>>(&self,
+                                        dest_dir: P,
+                                        concurrency: usize,
+                                        client: &BintrayClient)
+        -> Result<Vec<PathBuf>, BintrayError>
+    {
+        let files = self.list_files(false, client)?;
+        let dest_dir = dest_dir.as_ref();
+
+        let report = content::download_many(
+            &files, dest_dir, concurrency, 0, |_, _| { }, client);
+        if let Some((_, e)) = report.failed.into_iter().next() {
+            return Err(e);
+        }
+
+        info!("DownloadVersion({}): {} file(s) downloaded to {}",
+              self, report.downloaded.len(), dest_dir.display());
+        Ok(report.downloaded)
+    }
+}
+
+/// Compares two version strings.
+///
+/// They are compared as version numbers (e.g. `1.10` is greater than
+/// `1.9`). If they can't be compared this way, or if they are
+/// equivalent (e.g. `1.0` and `1.0.0`), they are compared as strings.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    match VersionCompare::compare(a, b).ok().and_then(|op| op.ord()) {
+        Some(Ordering::Equal) | None => a.cmp(b),
+        Some(ordering)               => ordering,
+    }
+}
+
+/// Versions are ordered by their `version` field first, so versions of
+/// different packages are still comparable. Version strings which can
+/// be parsed as version numbers come first and are ordered using
+/// `compare_versions()`; the others come last and are compared as
+/// strings, which keeps the order transitive when both kinds are mixed.
+///
+/// Versions with the same `version` string are then ordered by their
+/// other fields, so two versions are ordered as equal only if they are
+/// equal.
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Version) -> Ordering {
+        let is_number = |version: &Version| {
+            version_compare::Version::from(&version.version).is_some()
+        };
+
+        is_number(other).cmp(&is_number(self))
+            .then_with(|| compare_versions(&self.version, &other.version))
+            .then_with(|| self.owner.cmp(&other.owner))
+            .then_with(|| self.repository.cmp(&other.repository))
+            .then_with(|| self.package.cmp(&other.package))
+            .then_with(|| self.desc.cmp(&other.desc))
+            .then_with(|| self.labels.cmp(&other.labels))
+            .then_with(|| self.published.cmp(&other.published))
+            .then_with(|| self.created.cmp(&other.created))
+            .then_with(|| self.updated.cmp(&other.updated))
+            .then_with(|| self.released.cmp(&other.released))
+            .then_with(|| self.ordinal.cmp(&other.ordinal))
+            .then_with(|| self.rating_count.cmp(&other.rating_count))
+            .then_with(|| self.vcs_tag.cmp(&other.vcs_tag))
+            .then_with(|| {
+                self.github_release_notes_file
+                    .cmp(&other.github_release_notes_file)
+            })
+            .then_with(|| {
+                self.github_use_tag_release_notes
+                    .cmp(&other.github_use_tag_release_notes)
+            })
+            .then_with(|| self.attribute_names.cmp(&other.attribute_names))
+            .then_with(|| sorted_attributes(self).cmp(&sorted_attributes(other)))
+    }
+}
+
+/// Returns the attributes of `version` sorted by name, to compare them.
+fn sorted_attributes(version: &Version) -> Option<Vec<(&String, &String)>> {
+    version.attributes.as_ref().map(|attributes| {
+        let mut attributes: Vec<_> = attributes.iter().collect();
+        attributes.sort();
+        attributes
+    })
+}
+
+impl fmt::Display for ReleaseSummary {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "version: {}", self.version)?;
+        writeln!(f, "published: {}", self.published)?;
+        writeln!(f, "files: {}", self.file_count)?;
+        writeln!(f, "total_size: {}", self.total_size)?;
+        write!(f, "signed: {}", self.signed)?;
+        for (path, sha1) in &self.checksums {
+            write!(f, "\nfile: {} {}", sha1, path.display())?;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for Version {
@@ -1,9 +1,149 @@
 extern crate bintray;
 extern crate env_logger;
+extern crate hyper;
 
-use bintray::client::BintrayClient;
-use bintray::repository::Repository;
+use bintray::client::{BintrayClient, BintrayError, OperationStatus,
+                      ServiceHealth};
+use bintray::content::{self, ChecksumAlgorithm, Content, FileStatus,
+                       UploadOptions, UploadResult};
+use bintray::mock::{MockClock, MockResponse, MockTransport};
+use bintray::package::{Package, VersionSortStrategy};
+use bintray::repository::{PackageOrder, Repository, RepositoryType};
+use bintray::subject::{Subject, SubjectKind};
+use bintray::utils;
+use bintray::version::{PublishOptions, PublishResult, Version};
+use hyper::method::Method;
+use hyper::Url;
 use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::ops::Deref;
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A temporary directory unique to a test and to this process, removed
+/// when dropped, even if the test fails.
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(test_name: &str) -> TestDir {
+        let path = env::temp_dir().join(
+            format!("bintray-{}-{}", test_name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TestDir(path)
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A file of `my-company/my-repo/my-package`, as listed by Bintray.
+struct FileJson {
+    version: String,
+    path: String,
+    created: String,
+    size: u64,
+    sha1: String,
+}
+
+impl FileJson {
+    fn new(version: &str, path: &str) -> FileJson {
+        FileJson {
+            version: String::from(version),
+            path: String::from(path),
+            created: String::from("2017-03-01T12:34:56.789Z"),
+            size: 1,
+            sha1: String::from("00"),
+        }
+    }
+
+    fn set_created(mut self, created: &str) -> FileJson {
+        self.created = String::from(created);
+        self
+    }
+
+    fn set_size(mut self, size: u64) -> FileJson {
+        self.size = size;
+        self
+    }
+
+    fn set_sha1(mut self, sha1: &str) -> FileJson {
+        self.sha1 = String::from(sha1);
+        self
+    }
+
+    fn to_json(&self) -> String {
+        let name = self.path.rsplit('/').next().unwrap();
+        format!(r#"{{
+            "owner": "my-company", "repo": "my-repo",
+            "package": "my-package", "version": "{}",
+            "name": "{}", "path": "{}", "created": "{}",
+            "size": {}, "sha1": "{}"
+        }}"#, self.version, name, self.path, self.created, self.size,
+                self.sha1)
+    }
+}
+
+/// Returns the JSON array of a file listing.
+fn files_json(files: &[FileJson]) -> String {
+    let files: Vec<String> = files.iter().map(FileJson::to_json).collect();
+    format!("[{}]", files.join(","))
+}
+
+/// Returns `extra` JSON fields prefixed with a comma, to append them to
+/// other fields, or nothing if there are none.
+fn extra_fields(extra: &str) -> String {
+    if extra.is_empty() {
+        String::new()
+    } else {
+        format!(", {}", extra)
+    }
+}
+
+/// Returns the JSON of `my-company/my-repo` with the given type, followed
+/// by the `extra` fields, if any.
+fn repository_json(type_: &str, extra: &str) -> String {
+    format!(r#"{{
+        "owner": "my-company", "name": "my-repo", "type": "{}",
+        "premium": false, "created": "2017-03-01T12:34:56.789Z",
+        "package_count": 0, "private": false,
+        "gpg_sign_metadata": false, "gpg_sign_files": false,
+        "gpg_use_owner_key": false{}
+    }}"#, type_, extra_fields(extra))
+}
+
+/// Returns the JSON of the package `name` of `my-company/my-repo`,
+/// followed by the `extra` fields, if any.
+fn package_json(name: &str, extra: &str) -> String {
+    format!(r#"{{
+        "owner": "my-company", "repo": "my-repo", "name": "{}",
+        "public_download_numbers": false, "public_stats": false{}
+    }}"#, name, extra_fields(extra))
+}
+
+/// Returns the JSON of the version `name` of
+/// `my-company/my-repo/my-package`, followed by the `extra` fields, if
+/// any.
+fn version_json(name: &str, extra: &str) -> String {
+    format!(r#"{{
+        "owner": "my-company", "repo": "my-repo",
+        "package": "my-package", "name": "{}"{}
+    }}"#, name, extra_fields(extra))
+}
 
 #[test]
 fn create_update_and_delete_repository() {
@@ -78,3 +218,2590 @@ fn create_update_and_delete_repository() {
         assert!(repository.labels.is_none());
     }
 }
+
+#[test]
+fn sort_versions() {
+    let mut versions: Vec<Version> = ["1.10.0", "1.9.10", "1.9.2", "1.9"]
+        .iter()
+        .map(|v| Version::new("my-company", "my-repo", "my-package", v))
+        .collect();
+    versions.sort();
+
+    let sorted: Vec<&str> = versions.iter()
+        .map(|v| v.version.as_str())
+        .collect();
+    assert_eq!(sorted, vec!["1.9", "1.9.2", "1.9.10", "1.10.0"]);
+}
+
+#[test]
+fn sort_versions_total_order() {
+    let version = |package: &str, v: &str| {
+        Version::new("my-company", "my-repo", package, v)
+    };
+    let mut versions = vec![version("my-package", "snapshot"),
+                            version("other-package", "1.9"),
+                            version("my-package", "1.10"),
+                            version("my-package", "1.9")];
+    versions.sort();
+
+    let sorted: Vec<(&str, &str)> = versions.iter()
+        .map(|v| (v.package.as_str(), v.version.as_str()))
+        .collect();
+    assert_eq!(sorted, vec![("my-package", "1.9"),
+                            ("other-package", "1.9"),
+                            ("my-package", "1.10"),
+                            ("my-package", "snapshot")]);
+
+    let a = version("my-package", "1.9");
+    let mut b = a.clone();
+    b.desc = Some(String::from("Described"));
+    assert_ne!(a.cmp(&b), std::cmp::Ordering::Equal);
+    assert_eq!(a.cmp(&a.clone()), std::cmp::Ordering::Equal);
+}
+
+#[test]
+fn package_sorted_versions() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/packages/my-company/my-repo/my-package",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "repo": "my-repo",
+                "name": "my-package",
+                "public_download_numbers": false, "public_stats": false,
+                "versions": ["1.9.2", "1.10.0", "1.9.10"],
+                "latest_version": "1.10.0"
+            }"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let mut package = Package::new("my-company", "my-repo", "my-package");
+    package.get(false, &client).unwrap();
+    assert_eq!(package.versions, vec!["1.9.10", "1.10.0", "1.9.2"]);
+    assert_eq!(package.sorted_versions(),
+               vec!["1.9.2", "1.9.10", "1.10.0"]);
+
+    let package = package
+        .set_version_sort_strategy(VersionSortStrategy::Lexicographic);
+    assert_eq!(package.sorted_versions(),
+               vec!["1.10.0", "1.9.10", "1.9.2"]);
+    assert_eq!(package.sorted_versions_by(|a, b| b.len().cmp(&a.len())),
+               vec!["1.9.10", "1.10.0", "1.9.2"]);
+}
+
+#[test]
+fn repository_business_unit() {
+    // Business units are only supported by business accounts: the test
+    // is skipped unless such a subject is provided.
+    let owner = match env::var("BINTRAY_BUSINESS_OWNER") {
+        Ok(owner) => owner,
+        Err(_)    => return,
+    };
+    let username = env::var("BINTRAY_USERNAME").ok();
+    let api_key = env::var("BINTRAY_API_KEY").ok();
+    let repository_name = "t-bintray-crate-integration-business-unit";
+
+    let client = BintrayClient::new(username, api_key);
+
+    {
+        let mut repository = Repository::new(&owner, &repository_name)
+            .set_business_unit("team-1");
+        assert!(repository.delete(&client).is_ok());
+        assert!(repository.create(&client).is_ok());
+    }
+
+    {
+        let mut repository = Repository::new(&owner, &repository_name);
+        assert!(repository.get(&client).is_ok());
+        assert_eq!(repository.business_unit.as_ref().unwrap(), "team-1");
+
+        repository.business_unit = Some(String::from("team-2"));
+        assert!(repository.update(&client).is_ok());
+    }
+
+    {
+        let mut repository = Repository::new(&owner, &repository_name);
+        assert!(repository.get(&client).is_ok());
+        assert_eq!(repository.business_unit.as_ref().unwrap(), "team-2");
+        assert!(repository.delete(&client).is_ok());
+    }
+}
+
+#[test]
+fn create_repository_business_unit() {
+    let transport = MockTransport::new()
+        .on(Method::Post, "/repos/my-company/my-repo",
+            MockResponse::new(201, &repository_json("generic", "")));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    assert!(repository.create(&client).is_ok());
+    let mut repository = Repository::new("my-company", "my-repo")
+        .set_business_unit("team-1");
+    assert!(repository.create(&client).is_ok());
+
+    let bodies: Vec<String> = transport.get_requests().into_iter()
+        .map(|request| String::from_utf8(request.body).unwrap())
+        .collect();
+    assert!(!bodies[0].contains("business_unit"));
+    assert!(bodies[1].contains(r#""business_unit": "team-1""#));
+}
+
+#[test]
+fn get_repository_all_fields() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "name": "my-repo", "type": "deb",
+                "premium": true, "created": "2017-03-01T12:34:56.789Z",
+                "package_count": 3, "private": true,
+                "business_unit": "team-1", "desc": "My repository",
+                "labels": ["label-2", "label-1"],
+                "gpg_sign_metadata": true, "gpg_sign_files": true,
+                "gpg_use_owner_key": true,
+                "default_debian_architecture": "amd64",
+                "default_debian_distribution": "stretch",
+                "default_debian_component": "main",
+                "yum_metadata_depth": 2,
+                "yum_groups_file": "comps.xml"
+            }"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    assert!(repository.get(&client).is_ok());
+
+    let mut expected = Repository::new("my-company", "my-repo")
+        .set_labels(&["label-1", "label-2"])
+        .set_business_unit("team-1");
+    expected.type_ = RepositoryType::Debian;
+    expected.premium = true;
+    expected.created = Some(String::from("2017-03-01T12:34:56.789Z"));
+    expected.package_count = 3;
+    expected.private = true;
+    expected.desc = Some(String::from("My repository"));
+    expected.gpg_sign_metadata = true;
+    expected.gpg_sign_files = true;
+    expected.gpg_use_owner_key = true;
+    expected.default_debian_architecture = Some(String::from("amd64"));
+    expected.default_debian_distribution = Some(String::from("stretch"));
+    expected.default_debian_component = Some(String::from("main"));
+    expected.yum_metadata_depth = Some(2);
+    expected.yum_groups_file = Some(String::from("comps.xml"));
+    assert_eq!(repository, expected);
+}
+
+#[test]
+fn mock_client() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(404, r#"{"message": "Repo not found"}"#))
+        .on(Method::Delete, "/repos/my-company/my-repo",
+            MockResponse::new(200, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(
+        Some(String::from("user")), Some(String::from("key")),
+        transport.clone());
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    assert!(!repository.exists(&client).unwrap());
+    assert!(repository.delete(&client).is_ok());
+
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].method, Method::Get);
+    assert_eq!(requests[1].method, Method::Delete);
+    assert_eq!(requests[1].path, "/repos/my-company/my-repo");
+    assert!(requests[1].get_header("Authorization").is_some());
+}
+
+#[test]
+fn list_packages_ordered() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo/packages",
+            MockResponse::new(200, r#"[
+                {"name": "b", "linked": false},
+                {"name": "c", "linked": false},
+                {"name": "a", "linked": false}
+            ]"#))
+        .on(Method::Get, "/packages/my-company/my-repo/a",
+            MockResponse::new(200, &package_json("a", r#"
+                "created": "2017-01-01T00:00:00.000Z",
+                "updated": "2017-03-01T00:00:00.000Z", "versions": []"#)))
+        .on(Method::Get, "/packages/my-company/my-repo/b",
+            MockResponse::new(200, &package_json("b", r#"
+                "created": "2017-02-01T00:00:00.000Z",
+                "updated": "2017-02-01T00:00:00.000Z", "versions": []"#)))
+        .on(Method::Get, "/packages/my-company/my-repo/c",
+            MockResponse::new(200, &package_json("c", r#"
+                "created": "2017-03-01T00:00:00.000Z",
+                "updated": "2017-03-01T00:00:00.000Z", "versions": []"#)));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let repository = Repository::new("my-company", "my-repo");
+    assert_eq!(
+        repository.list_packages_ordered(PackageOrder::Name, &client).unwrap(),
+        vec!["a", "b", "c"]);
+    assert_eq!(
+        repository.list_packages_ordered(PackageOrder::CreatedDesc, &client)
+            .unwrap(),
+        vec!["c", "b", "a"]);
+    assert_eq!(
+        repository.list_packages_ordered(PackageOrder::UpdatedDesc, &client)
+            .unwrap(),
+        vec!["a", "c", "b"]);
+}
+
+#[test]
+fn repository_packages() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo/packages",
+            MockResponse::new(200, r#"[
+                {"name": "a", "linked": false},
+                {"name": "b", "linked": true}
+            ]"#))
+        .on(Method::Get, "/packages/my-company/my-repo/a",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "repo": "my-repo", "name": "a",
+                "desc": "Package a",
+                "public_download_numbers": false, "public_stats": false,
+                "versions": []
+            }"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let repository = Repository::new("my-company", "my-repo");
+    let mut packages = repository.packages(&client).unwrap();
+    let names: Vec<&str> = packages.iter()
+        .map(|package| package.package.as_str())
+        .collect();
+    assert_eq!(names, vec!["a", "b"]);
+    assert!(packages.iter().all(|package| package.partial));
+    assert!(packages[0].desc.is_none());
+
+    packages[0].get(false, &client).unwrap();
+    assert!(!packages[0].partial);
+    assert_eq!(packages[0].desc.as_ref().unwrap(), "Package a");
+}
+
+#[test]
+fn repository_package_names_with_linked() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo/packages",
+            MockResponse::new(200, r#"[
+                {"name": "owned", "linked": false},
+                {"name": "from-elsewhere", "linked": true}
+            ]"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let repository = Repository::new("my-company", "my-repo");
+    let packages = repository.package_names_with_linked(&client).unwrap();
+    assert_eq!(packages, vec![(String::from("owned"), false),
+                              (String::from("from-elsewhere"), true)]);
+}
+
+#[test]
+fn set_labels_and_licenses_from_csv() {
+    let package = Package::new("my-company", "my-repo", "my-package")
+        .set_labels_csv(" label-2,label-1 , ,")
+        .set_licenses_csv("MPL-1.1,,  Apache-2.0  ");
+    assert_eq!(package.labels.unwrap(), vec!["label-1", "label-2"]);
+    assert_eq!(package.licenses.unwrap(), vec!["Apache-2.0", "MPL-1.1"]);
+
+    let repository = Repository::new("my-company", "my-repo")
+        .set_labels_csv("");
+    assert_eq!(repository.labels.unwrap(), Vec::<String>::new());
+
+    let repository = Repository::new("my-company", "my-repo")
+        .set_labels_csv("label-1");
+    assert_eq!(repository.labels.unwrap(), vec!["label-1"]);
+}
+
+#[test]
+fn calc_metadata_pending() {
+    let transport = MockTransport::new()
+        .on(Method::Post, "/calc_metadata/my-company/my-repo",
+            MockResponse::new(202, r#"{"message": "accepted"}"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let repository = Repository::new("my-company", "my-repo");
+    assert_eq!(repository.calc_metadata(&client).unwrap(),
+               OperationStatus::Pending);
+}
+
+#[test]
+fn list_content_siblings() {
+    let transport = MockTransport::new()
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "my-package.tar.gz"),
+                FileJson::new("1.0.0", "my-package.zip"),
+            ])));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "/my-package.zip");
+    let siblings = content.siblings(false, &client).unwrap();
+    assert_eq!(siblings.len(), 1);
+    assert_eq!(siblings[0].path.to_str().unwrap(), "my-package.tar.gz");
+}
+
+#[test]
+fn checksum_from_reader() {
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt");
+    content.set_checksum_from_reader(&b"hello\n"[..]).unwrap();
+    assert_eq!(content.sha1.as_ref().unwrap(),
+               "f572d396fae9206628714fb2ce00f72e94f2258f");
+    assert_eq!(content.sha256.as_ref().unwrap(),
+               "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03");
+}
+
+#[test]
+fn download_content_range() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/my-company/my-repo/archive.zip",
+            MockResponse::new(206, "PK")
+            .header("Content-Range", "bytes 0-1/1024"))
+        .on(Method::Get, "/my-company/my-repo/ignored.zip",
+            MockResponse::new(200, "PK\x03\x04"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "archive.zip");
+    assert_eq!(content.download_range(0, 2, &client).unwrap(), b"PK");
+    assert_eq!(transport.get_requests()[0].get_header("Range"),
+               Some("bytes=0-1"));
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "ignored.zip");
+    assert!(content.download_range(0, 2, &client).is_err());
+
+    // A range past the largest offset is refused before any request.
+    let requests = transport.get_requests().len();
+    match content.download_range(u64::MAX, 2, &client) {
+        Err(BintrayError::Io(ref e))
+            if e.kind() == io::ErrorKind::InvalidInput => { }
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert_eq!(transport.get_requests().len(), requests);
+
+    // A range ending exactly at the largest offset is still requested.
+    assert!(content.download_range(u64::MAX - 1, 2, &client).is_err());
+    let requests = transport.get_requests();
+    assert_eq!(requests.last().unwrap().get_header("Range"),
+               Some("bytes=18446744073709551614-18446744073709551615"));
+
+    // An empty range needs no request.
+    assert_eq!(content.download_range(u64::MAX, 0, &client).unwrap(),
+               Vec::<u8>::new());
+    assert_eq!(transport.get_requests().len(), requests.len());
+}
+
+#[test]
+fn download_content_parallel() {
+    let test_dir = TestDir::new("download_content_parallel");
+    let data: String = (0..1000).map(|i| format!("{:04}\n", i)).collect();
+    let sha256 = {
+        let local_filename = test_dir.join("parallel-source");
+        fs::write(&local_filename, &data).unwrap();
+        let mut content = Content::new(
+            "my-company", "my-repo", "my-package", "1.0.0", "data.txt");
+        content.set_checksum_from_file(&local_filename).unwrap();
+        fs::remove_file(&local_filename).unwrap();
+        content.sha256.unwrap()
+    };
+
+    let response = MockResponse::new(200, &data)
+        .header("X-Checksum-Sha2", &sha256);
+    let transport = MockTransport::new()
+        .on(Method::Head, "/my-company/my-repo/data.txt", response.clone())
+        .on(Method::Get, "/my-company/my-repo/data.txt",
+            response.clone().accept_ranges())
+        .on(Method::Head, "/my-company/my-repo/no-range.txt", response.clone())
+        .on(Method::Get, "/my-company/my-repo/no-range.txt", response.clone())
+        .on(Method::Head, "/my-company/my-repo/corrupted.txt",
+            MockResponse::new(200, &data)
+            .header("X-Checksum-Sha2", &"0".repeat(64)))
+        .on(Method::Get, "/my-company/my-repo/corrupted.txt",
+            response.accept_ranges())
+        .on(Method::Head, "/my-company/my-repo/tiny.txt",
+            MockResponse::new(200, "abc"))
+        .on(Method::Get, "/my-company/my-repo/tiny.txt",
+            MockResponse::new(200, "abc").accept_ranges());
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let local_filename = test_dir.join("parallel");
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "data.txt");
+    content.download_parallel(&local_filename, 7, 3, &client).unwrap();
+    assert_eq!(fs::read_to_string(&local_filename).unwrap(), data);
+    let mut ranges: Vec<String> = transport.get_requests().into_iter()
+        .filter(|request| request.method == Method::Get)
+        .map(|request| String::from(request.get_header("Range").unwrap()))
+        .collect();
+    ranges.sort();
+    assert_eq!(ranges, vec!["bytes=0-714", "bytes=1430-2144",
+                            "bytes=2145-2859", "bytes=2860-3574",
+                            "bytes=3575-4289", "bytes=4290-4999",
+                            "bytes=715-1429"]);
+    fs::remove_file(&local_filename).unwrap();
+
+    // More chunks than bytes: no empty range is requested.
+    let requests = transport.get_requests().len();
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "tiny.txt");
+    content.download_parallel(&local_filename, 8, 3, &client).unwrap();
+    assert_eq!(fs::read_to_string(&local_filename).unwrap(), "abc");
+    let mut ranges: Vec<String> = transport.get_requests()[requests..].iter()
+        .filter(|request| request.method == Method::Get)
+        .map(|request| String::from(request.get_header("Range").unwrap()))
+        .collect();
+    ranges.sort();
+    assert_eq!(ranges, vec!["bytes=0-0", "bytes=1-1", "bytes=2-2"]);
+    fs::remove_file(&local_filename).unwrap();
+
+    // The server ignores Range requests: the first response holds the
+    // whole file.
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "no-range.txt");
+    content.download_parallel(&local_filename, 7, 3, &client).unwrap();
+    assert_eq!(fs::read_to_string(&local_filename).unwrap(), data);
+    fs::remove_file(&local_filename).unwrap();
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "corrupted.txt");
+    assert!(content.download_parallel(&local_filename, 7, 3, &client)
+            .is_err());
+    assert!(!local_filename.exists());
+}
+
+#[test]
+fn download_content_cached() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/my-company/my-repo/data.txt",
+            MockResponse::new(200, "data").header("ETag", "\"v1\""))
+        .on(Method::Get, "/my-company/my-repo/data.txt",
+            MockResponse::new(304, ""));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("download_content_cached");
+    let cache_dir = test_dir.join("cache");
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "data.txt");
+
+    let local_filename = content.download_cached(&cache_dir, &client).unwrap();
+    assert_eq!(local_filename, cache_dir.join("my-company/my-repo/data.txt"));
+    assert_eq!(fs::read_to_string(&local_filename).unwrap(), "data");
+
+    let cached = content.download_cached(&cache_dir, &client).unwrap();
+    assert_eq!(cached, local_filename);
+    assert_eq!(fs::read_to_string(&cached).unwrap(), "data");
+
+    let if_none_match: Vec<Option<String>> = transport.get_requests().iter()
+        .map(|request| request.get_header("If-None-Match").map(String::from))
+        .collect();
+    assert_eq!(if_none_match, vec![None, Some(String::from("\"v1\""))]);
+}
+
+#[test]
+fn download_content_cached_rejects_unsafe_paths() {
+    let transport = MockTransport::new();
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("download_content_cached_rejects_unsafe_paths");
+    let cache_dir = test_dir.join("cache");
+    let unsafe_contents = vec![
+        Content::new("my-company", "my-repo", "my-package", "1.0.0",
+                     "a/../../../x.txt"),
+        Content::new("..", "my-repo", "my-package", "1.0.0", "x.txt"),
+        Content::new("my-company", "../..", "my-package", "1.0.0", "x.txt"),
+    ];
+    for content in &unsafe_contents {
+        match content.download_cached(&cache_dir, &client) {
+            Err(BintrayError::UnsafePath { .. }) => {}
+            _ => panic!("Expected an UnsafePath error for {}", content),
+        }
+    }
+    assert!(transport.get_requests().is_empty());
+    assert!(!cache_dir.exists());
+}
+
+#[test]
+fn validate_names() {
+    assert!(Repository::try_new("my-company", "my-repo").is_ok());
+    assert!(Package::try_new("my-company", "my-repo", "org.example:lib")
+            .is_ok());
+    assert!(Version::try_new("my-company", "my-repo", "my-package",
+                             "1.0.0-rc.1+build~2").is_ok());
+
+    assert!(Repository::try_new("my-company", "").is_err());
+    assert!(Repository::try_new("my-company", "..").is_err());
+    assert!(Package::try_new("my-company", "my-repo", "my/package").is_err());
+    assert!(Version::try_new("my-company", "my-repo", "my-package",
+                             "1.0 beta").is_err());
+    assert!(Repository::try_new(&"a".repeat(256), "my-repo").is_err());
+
+    match Package::try_new("my-company", "my-repo", "my/package") {
+        Err(BintrayError::InvalidName { name, .. }) => {
+            assert_eq!(name, "my/package")
+        }
+        _ => panic!("Expected an InvalidName error"),
+    }
+}
+
+#[test]
+fn list_content_entitlements() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo/entitlements",
+            MockResponse::new(200, r#"[
+                {"id": "e1", "access": "rw", "path": "pool"},
+                {"id": "e2", "access": "r", "path": "pool/main"},
+                {"id": "e3", "access": "r", "path": "dists"},
+                {"id": "e4", "access": "r"}
+            ]"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0",
+        "/pool/main/my-package.deb");
+    let ids: Vec<String> = content.entitlements(&client).unwrap()
+        .into_iter()
+        .map(|entitlement| entitlement.id)
+        .collect();
+    assert_eq!(ids, vec!["e2", "e4", "e1"]);
+}
+
+#[test]
+fn glob_match() {
+    assert!(utils::glob_match("*.deb", "my-package_1.0.0_amd64.deb"));
+    assert!(utils::glob_match("my-package_?.?.?_*", "my-package_1.0.0_all.deb"));
+    assert!(utils::glob_match("*", ""));
+    assert!(utils::glob_match("a*b*c", "aXbYbZc"));
+    assert!(!utils::glob_match("*.deb", "my-package.rpm"));
+    assert!(!utils::glob_match("?", ""));
+    assert!(!utils::glob_match("a*b", "aXbY"));
+}
+
+#[test]
+fn get_package_metrics() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/packages/my-company/my-repo/my-package",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "repo": "my-repo",
+                "name": "my-package",
+                "public_download_numbers": false, "public_stats": false,
+                "versions": [],
+                "rating": 4, "rating_count": 12, "followers_count": 34,
+                "system_ids": ["pkg:b", "pkg:a"]
+            }"#))
+        .on(Method::Get, "/packages/my-company/my-repo/my-package",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "repo": "my-repo",
+                "name": "my-package",
+                "public_download_numbers": false, "public_stats": false,
+                "versions": []
+            }"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let mut package = Package::new("my-company", "my-repo", "my-package");
+    package.get(false, &client).unwrap();
+    assert_eq!(package.rating_count, Some(12));
+    assert_eq!(package.followers_count, Some(34));
+    assert_eq!(package.system_ids,
+               Some(vec![String::from("pkg:a"), String::from("pkg:b")]));
+
+    package.get(false, &client).unwrap();
+    assert_eq!(package.rating_count, None);
+    assert_eq!(package.followers_count, None);
+    assert_eq!(package.system_ids, None);
+}
+
+#[test]
+fn download_latest() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/packages/my-company/my-repo/my-package",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "repo": "my-repo",
+                "name": "my-package",
+                "public_download_numbers": false, "public_stats": false,
+                "versions": ["1.10.0", "1.9.0"]
+            }"#))
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.10.0/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.10.0", "1.10.0/hello.txt")
+                    .set_size(6)
+                    .set_sha1("f572d396fae9206628714fb2ce00f72e94f2258f"),
+            ])))
+        .on(Method::Get, "/my-company/my-repo/1.10.0/hello.txt",
+            MockResponse::new(200, "hello\n"));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let dest_dir = TestDir::new("download_latest");
+    let local_filename = client.download_latest(
+        "my-company", "my-repo", "my-package", "*.txt", &dest_dir).unwrap();
+    assert_eq!(local_filename, dest_dir.join("hello.txt"));
+    assert_eq!(fs::read_to_string(&local_filename).unwrap(), "hello\n");
+
+    assert!(client.download_latest(
+            "my-company", "my-repo", "my-package", "*.deb", &dest_dir)
+            .is_err());
+}
+
+#[test]
+fn ensure_repository() {
+    let desired = || {
+        let mut repository = Repository::new("my-company", "my-repo");
+        repository.type_ = RepositoryType::Rpm;
+        repository.desc = Some(String::from("New description"));
+        repository
+    };
+
+    // The repository doesn't exist: it is created.
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(404, r#"{"message": "Repo not found"}"#))
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json(
+                    "rpm", r#""desc": "New description""#)))
+        .on(Method::Post, "/repos/my-company/my-repo",
+            MockResponse::new(201, &repository_json(
+                    "rpm", r#""desc": "New description""#)));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut repository = desired();
+    assert!(repository.ensure(&client).unwrap());
+    assert!(repository.created.is_some());
+    let methods: Vec<Method> = transport.get_requests().into_iter()
+        .map(|request| request.method)
+        .collect();
+    assert_eq!(methods, vec![Method::Get, Method::Post, Method::Get]);
+
+    // The repository exists with another description: it is updated.
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json(
+                    "rpm", r#""desc": "Old description""#)))
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json(
+                    "rpm", r#""desc": "New description""#)))
+        .on(Method::Patch, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json(
+                    "rpm", r#""desc": "New description""#)));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut repository = desired();
+    assert!(!repository.ensure(&client).unwrap());
+    assert_eq!(repository.desc.unwrap(), "New description");
+    let methods: Vec<Method> = transport.get_requests().into_iter()
+        .map(|request| request.method)
+        .collect();
+    assert_eq!(methods, vec![Method::Get, Method::Patch, Method::Get]);
+
+    // The repository exists with the same description and some labels,
+    // which are not set locally: it is left untouched.
+    let live = repository_json(
+        "rpm", r#""desc": "New description", "labels": ["a", "b"]"#);
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &live));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut repository = desired();
+    assert!(!repository.ensure(&client).unwrap());
+    assert_eq!(repository.labels.unwrap(), vec!["a", "b"]);
+    let methods: Vec<Method> = transport.get_requests().into_iter()
+        .map(|request| request.method)
+        .collect();
+    assert_eq!(methods, vec![Method::Get, Method::Get]);
+}
+
+#[test]
+fn download_verified_leaves_no_partial_file() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/my-company/my-repo/corrupted.txt",
+            MockResponse::new(200, "hell0\n"));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let test_dir = TestDir::new("download_verified_leaves_no_partial_file");
+    let dest_dir = test_dir.join("download-verified");
+    fs::create_dir_all(&dest_dir).unwrap();
+    let local_filename = dest_dir.join("corrupted.txt");
+
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "corrupted.txt");
+    content.sha1 = Some(String::from(
+            "f572d396fae9206628714fb2ce00f72e94f2258f"));
+    assert!(content.download_verified(&local_filename, &client).is_err());
+    assert_eq!(fs::read_dir(&dest_dir).unwrap().count(), 0);
+
+    assert!(content.download(&local_filename, &client).is_ok());
+    assert_eq!(fs::read_to_string(&local_filename).unwrap(), "hell0\n");
+    assert_eq!(fs::read_dir(&dest_dir).unwrap().count(), 1);
+}
+
+#[test]
+fn subject_kind() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/users/my-company",
+            MockResponse::new(404, r#"{"message": "User not found"}"#))
+        .on(Method::Get, "/orgs/my-company",
+            MockResponse::new(200, r#"{"name": "my-company"}"#))
+        .on(Method::Get, "/users/my-user",
+            MockResponse::new(200, r#"{"name": "my-user"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut subject = Subject::new("my-company");
+    assert_eq!(subject.kind(&client).unwrap(), SubjectKind::Organization);
+    assert_eq!(subject.kind(&client).unwrap(), SubjectKind::Organization);
+    assert_eq!(transport.get_requests().len(), 2);
+
+    let mut subject = Subject::new("my-user");
+    assert_eq!(subject.kind(&client).unwrap(), SubjectKind::User);
+
+    let mut subject = Subject::new("nobody");
+    assert!(subject.kind(&client).is_err());
+}
+
+#[test]
+fn upload_override_if_different() {
+    let transport = MockTransport::new()
+        .on(Method::Put, "/content/my-company/my-repo/my-package/1.0.0/hello.txt",
+            MockResponse::new(409, r#"{"message": "File unchanged"}"#))
+        .on(Method::Put, "/content/my-company/my-repo/my-package/1.0.0/published.txt",
+            MockResponse::new(409, r#"{"message": "Unable to override a published file"}"#))
+        .on(Method::Put, "/content/my-company/my-repo/my-package/1.0.0/same-checksum.txt",
+            MockResponse::new(409, r#"{"message": "File exists"}"#)
+            .header("X-Checksum-Sha2",
+                    "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_override_if_different");
+    let local_filename = test_dir.join("override-if-different");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt")
+        .set_override_if_different(true);
+    let no_values: &[&str] = &[];
+    let uploaded = content.upload_if_changed(
+        &local_filename, false, None, no_values, no_values, no_values,
+        &client).unwrap();
+    assert!(!uploaded);
+
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path,
+               "/content/my-company/my-repo/my-package/1.0.0/hello.txt?override=1");
+    assert_eq!(requests[0].get_header("X-Checksum-Sha2"),
+               Some("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"));
+    assert_eq!(requests[0].body, b"hello\n");
+
+    // A remote checksum identical to the local one is enough.
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "same-checksum.txt")
+        .set_override_if_different(true);
+    assert!(!content.upload_if_changed(
+            &local_filename, false, None, no_values, no_values, no_values,
+            &client).unwrap());
+
+    // Other conflicts are still errors.
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "published.txt")
+        .set_override_if_different(true);
+    match content.upload_if_changed(
+        &local_filename, false, None, no_values, no_values, no_values,
+        &client) {
+        Err(BintrayError::Conflict { message, .. }) => {
+            assert_eq!(message, "Unable to override a published file");
+        }
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn content_from_url() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/file_version/my-company/my-repo/pool/my%20file.deb",
+            MockResponse::new(200, &version_json("1.0.0", "")));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let url = Url::parse(
+        "https://dl.bintray.com/my-company/my-repo/pool/my%20file.deb")
+        .unwrap();
+    let content = client.content_from_url(&url).unwrap();
+    assert_eq!(content, Content::new(
+            "my-company", "my-repo", "my-package", "1.0.0",
+            "pool/my file.deb"));
+
+    for url in &["https://example.com/my-company/my-repo/file.deb",
+                 "http://dl.bintray.com/my-company/my-repo/file.deb",
+                 "https://dl.bintray.com/my-company/my-repo",
+                 "https://dl.bintray.com/my-company/my-repo/"] {
+        let url = Url::parse(url).unwrap();
+        assert!(client.content_from_url(&url).is_err());
+    }
+}
+
+#[test]
+fn versions_with_metadata() {
+    let mut transport = MockTransport::new();
+    for name in &["1.10.0", "1.9.0", "1.9.10", "1.2.0"] {
+        transport = transport.on(
+            Method::Get,
+            &format!("/packages/my-company/my-repo/my-package/versions/{}",
+                     name),
+            MockResponse::new(200, &version_json(
+                name, &format!(r#""desc": "Release {}""#, name))));
+    }
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut package = Package::new("my-company", "my-repo", "my-package");
+    package.versions = vec!["1.10.0", "1.9.0", "1.9.10", "1.2.0"].into_iter()
+        .map(String::from)
+        .collect();
+
+    let versions = package.versions_with_metadata(false, 3, &client).unwrap();
+    let names: Vec<&str> = versions.iter()
+        .map(|v| v.version.as_str())
+        .collect();
+    assert_eq!(names, vec!["1.2.0", "1.9.0", "1.9.10", "1.10.0"]);
+    assert_eq!(versions[0].desc.as_ref().unwrap(), "Release 1.2.0");
+    assert_eq!(transport.get_requests().len(), 4);
+
+    package.versions.push(String::from("2.0.0"));
+    assert!(package.versions_with_metadata(false, 3, &client).is_err());
+}
+
+#[test]
+fn versions_with_label() {
+    let mut transport = MockTransport::new();
+    for &(name, labels) in &[("1.10.0", r#""latest-stable""#),
+                             ("1.9.0", ""),
+                             ("1.11.0-rc.1", r#""preview""#),
+                             ("1.2.0", r#""lts", "latest-stable""#)] {
+        transport = transport.on(
+            Method::Get,
+            &format!("/packages/my-company/my-repo/my-package/versions/{}",
+                     name),
+            MockResponse::new(200, &version_json(
+                name, &format!(r#""labels": [{}]"#, labels))));
+    }
+    let client = BintrayClient::mock(None, None, transport);
+
+    let mut package = Package::new("my-company", "my-repo", "my-package");
+    package.versions = vec!["1.10.0", "1.9.0", "1.11.0-rc.1", "1.2.0"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+    assert_eq!(package.versions_with_label("latest-stable", 2, &client)
+                   .unwrap(),
+               vec!["1.2.0", "1.10.0"]);
+    assert!(package.versions_with_label("unknown", 2, &client)
+                .unwrap()
+                .is_empty());
+}
+
+#[test]
+fn package_all_files() {
+    let transport = MockTransport::new()
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.10.0/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.10.0", "1.10.0/a.txt"),
+                FileJson::new("1.10.0", "1.10.0/b.txt"),
+            ])))
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.9.0/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.9.0", "1.9.0/a.txt"),
+            ])))
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.2.0/files",
+            MockResponse::new(200, &files_json(&[])));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut package = Package::new("my-company", "my-repo", "my-package");
+    package.versions = vec!["1.10.0", "1.9.0", "1.2.0"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+    let files: Vec<(String, PathBuf)> = package.all_files(2, &client)
+        .unwrap()
+        .into_iter()
+        .map(|(version, file)| (version, file.path))
+        .collect();
+    assert_eq!(files, vec![
+        (String::from("1.9.0"), PathBuf::from("1.9.0/a.txt")),
+        (String::from("1.10.0"), PathBuf::from("1.10.0/a.txt")),
+        (String::from("1.10.0"), PathBuf::from("1.10.0/b.txt")),
+    ]);
+    assert!(transport.get_requests().iter().all(|request| {
+        request.path.ends_with("?include_unpublished=1")
+    }));
+}
+
+#[test]
+fn parse_rfc3339() {
+    let time = UNIX_EPOCH + Duration::from_millis(1488371696789);
+    for timestamp in &["2017-03-01T12:34:56.789Z",
+                       "2017-03-01T12:34:56.789000Z",
+                       "2017-03-01T13:34:56.789+01:00",
+                       "2017-03-01t10:04:56.789-02:30"] {
+        assert_eq!(utils::parse_rfc3339(timestamp), Some(time));
+    }
+    assert_eq!(utils::parse_rfc3339(&utils::format_rfc3339(time)),
+               Some(time));
+    assert_eq!(utils::parse_rfc3339("1970-01-01T00:00:00Z"),
+               Some(UNIX_EPOCH));
+    assert_eq!(utils::parse_rfc3339("1969-12-31T23:59:59Z"),
+               Some(UNIX_EPOCH - Duration::from_secs(1)));
+
+    for timestamp in &["", "2017-03-01", "2017-03-01T12:34:56",
+                       "2017-13-01T12:34:56Z", "2017-03-01T12:34:56+0100",
+                       "2017-03-01T12:34:56.Z", "not a timestamp at all"] {
+        assert_eq!(utils::parse_rfc3339(timestamp), None, "{}", timestamp);
+    }
+}
+
+#[test]
+fn version_is_expired() {
+    let ttl = |date: &str| {
+        MockResponse::new(200, &format!(r#"[{{
+            "name": "delete_after", "values": ["{}"], "type": "date"
+        }}]"#, date))
+    };
+    let path = "/packages/my-company/my-repo/my-package/versions/1.0.0/attributes";
+    let transport = MockTransport::new()
+        .on(Method::Get, path, ttl("2017-03-01T13:34:56+01:00"))
+        .on(Method::Get, path, ttl("2999-01-01T00:00:00Z"))
+        .on(Method::Get, path, MockResponse::new(200, "[]"))
+        .on(Method::Get, path, ttl("1st of March"));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let version = Version::new("my-company", "my-repo", "my-package", "1.0.0");
+    assert!(version.is_expired(&client).unwrap());
+    assert!(!version.is_expired(&client).unwrap());
+    assert!(!version.is_expired(&client).unwrap());
+    match version.is_expired(&client) {
+        Err(BintrayError::Io(ref e))
+            if e.kind() == io::ErrorKind::InvalidData => { }
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn version_released_now() {
+    let before = utils::format_rfc3339(SystemTime::now());
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0")
+        .released_now();
+    let after = utils::format_rfc3339(SystemTime::now());
+
+    let released = version.released.unwrap();
+    assert!(before <= released && released <= after);
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0")
+        .set_released(UNIX_EPOCH + Duration::from_secs(1488371696));
+    assert_eq!(version.released.unwrap(), "2017-03-01T12:34:56.000Z");
+}
+
+#[test]
+fn create_version_released() {
+    let transport = MockTransport::new()
+        .on(Method::Post, "/packages/my-company/my-repo/my-package/versions",
+            MockResponse::new(201, &version_json("1.0.0", r#"
+                "created": "2017-03-01T12:34:56.789Z",
+                "released": "2017-03-01T11:34:56.000Z""#)))
+        .on(Method::Post, "/packages/my-company/my-repo/my-package/versions",
+            MockResponse::new(201, &version_json("1.0.0", r#"
+                "created": "2017-03-01T12:34:56.789Z", "released": """#)));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let mut version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    version.released = Some(String::from("2017-03-01T12:34:56+01:00"));
+    assert!(version.create(&client).is_ok());
+    assert_eq!(version.released.as_ref().unwrap(),
+               "2017-03-01T11:34:56.000Z");
+
+    // Without a release date in the response, the submitted one is kept.
+    version.released = Some(String::from("2017-03-01T12:34:56+01:00"));
+    assert!(version.create(&client).is_ok());
+    assert_eq!(version.released.as_ref().unwrap(),
+               "2017-03-01T12:34:56+01:00");
+}
+
+#[test]
+fn publish_content_warnings() {
+    let transport = MockTransport::new()
+        .on(Method::Post,
+            "/content/my-company/my-repo/my-package/1.0.0/publish",
+            MockResponse::new(200, r#"{"files": 2}"#))
+        .on(Method::Post,
+            "/content/my-company/my-repo/my-package/1.0.0/publish",
+            MockResponse::new(200, r#"{"files": 0, "warn": "Nothing to do"}"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    assert_eq!(version.publish_content(None, false, &client).unwrap(),
+               OperationStatus::Done(PublishResult {
+                   files: 2,
+                   warnings: vec![],
+               }));
+    assert_eq!(version.publish_content(None, false, &client).unwrap(),
+               OperationStatus::Done(PublishResult {
+                   files: 0,
+                   warnings: vec![String::from("Nothing to do"),
+                                  String::from("No files published")],
+               }));
+}
+
+#[test]
+fn repository_being_deleted() {
+    let being_deleted = || {
+        MockResponse::new(
+            409, r#"{"message": "Repository my-repo is being deleted"}"#)
+    };
+    let transport = MockTransport::new()
+        .on(Method::Post, "/repos/my-company/my-repo", being_deleted())
+        .on(Method::Get, "/repos/my-company/my-repo", being_deleted())
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(404, r#"{"message": "Repo not found"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    match repository.create(&client) {
+        Err(BintrayError::RepositoryBeingDeleted { repository }) => {
+            assert_eq!(repository, "my-company/my-repo")
+        }
+        _ => panic!("Expected a RepositoryBeingDeleted error"),
+    }
+
+    assert!(repository.wait_for_deletion(Duration::from_secs(10), &client)
+            .is_ok());
+    assert_eq!(transport.get_requests().len(), 3);
+}
+
+#[test]
+fn accept_header() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(404, r#"{"message": "Repo not found"}"#))
+        .on(Method::Get, "/my-company/my-repo/file.txt",
+            MockResponse::new(200, "Hello"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    assert!(!repository.exists(&client).unwrap());
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "file.txt");
+    assert!(content.download_range(0, 2, &client).is_err());
+
+    let client = BintrayClient::mock(None, None, transport.clone())
+        .set_accept("application/vnd.bintray.v2+json").unwrap();
+    assert!(!repository.exists(&client).unwrap());
+
+    let requests = transport.get_requests();
+    assert_eq!(requests[0].get_header("Accept"), Some("application/json"));
+    assert_eq!(requests[1].get_header("Accept"), None);
+    assert_eq!(requests[2].get_header("Accept"),
+               Some("application/vnd.bintray.v2+json"));
+
+    assert!(BintrayClient::mock(None, None, transport)
+            .set_accept("not a media type").is_err());
+}
+
+#[test]
+fn total_size() {
+    let transport = MockTransport::new()
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "file-0").set_size(1024),
+                FileJson::new("1.0.0", "file-1").set_size(2048),
+            ])))
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "file-0").set_size(1024),
+                FileJson::new("1.0.0", "file-1").set_size(2048),
+                FileJson::new("1.0.0", "file-2").set_size(4096),
+            ])));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let version = Version::new("my-company", "my-repo", "my-package", "1.0.0");
+    assert_eq!(version.total_size(&client).unwrap(), 3072);
+    let package = Package::new("my-company", "my-repo", "my-package");
+    assert_eq!(package.total_size(&client).unwrap(), 7168);
+
+    assert!(transport.get_requests().iter()
+            .all(|request| request.path.ends_with("?include_unpublished=1")));
+}
+
+#[test]
+fn show_version_in_download_list() {
+    let transport = MockTransport::new()
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0-rc.1/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0-rc.1", "file.txt"),
+            ])))
+        .on(Method::Put, "/file_metadata/my-company/my-repo/file.txt",
+            MockResponse::new(200, r#"{"message": "success"}"#))
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0-rc.2/files",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0-rc.2", "locked.txt"),
+            ])))
+        .on(Method::Put, "/file_metadata/my-company/my-repo/locked.txt",
+            MockResponse::new(403, r#"{"message": "Forbidden"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0-rc.1");
+    assert!(version.show_in_download_list(false, &client).is_ok());
+
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(String::from_utf8_lossy(&requests[1].body)
+               .replace(char::is_whitespace, ""),
+               r#"{"list_in_downloads":false}"#);
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0-rc.2");
+    match version.show_in_download_list(false, &client) {
+        Err(BintrayError::PermissionDenied { ref object, ref message }) => {
+            assert_eq!(object,
+                       "my-company/my-repo/my-package/1.0.0-rc.2/locked.txt");
+            assert_eq!(message, "Forbidden");
+        }
+        result => panic!("Expected a PermissionDenied error: {:?}", result),
+    }
+}
+
+#[test]
+fn content_from_coordinates() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json(
+                    "rpm", r#""yum_metadata_depth": 2"#)));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let content = client.content(
+        "my-company", "my-repo", "my-package", "1.0.0", "file.deb",
+        Some(&RepositoryType::Deb)).unwrap();
+    assert_eq!(content.repository_type, Some(RepositoryType::Debian));
+    assert!(transport.get_requests().is_empty());
+
+    let content = client.content(
+        "my-company", "my-repo", "my-package", "1.0.0", "file.rpm",
+        None).unwrap();
+    assert_eq!(content.repository_type, Some(RepositoryType::Rpm));
+    assert_eq!(content.yum_metadata_depth, Some(2));
+    assert_eq!(transport.get_requests().len(), 1);
+}
+
+#[test]
+fn wait_for_files_available() {
+    let transport = MockTransport::new()
+        .on(Method::Head, "/my-company/my-repo/a.txt",
+            MockResponse::new(200, ""))
+        .on(Method::Head, "/my-company/my-repo/b.txt",
+            MockResponse::new(200, ""));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let version = Version::new("my-company", "my-repo", "my-package", "1.0.0");
+    let paths: Vec<PathBuf> = ["a.txt", "missing.txt", "b.txt"].iter()
+        .map(PathBuf::from)
+        .collect();
+    let report = version.wait_for_files_available(
+        &paths, Duration::from_secs(0), 2, &client).unwrap();
+    assert_eq!(report, vec![(PathBuf::from("a.txt"), true),
+                            (PathBuf::from("missing.txt"), false),
+                            (PathBuf::from("b.txt"), true)]);
+}
+
+#[test]
+fn repository_type_cache() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json("deb", "")));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    for name in &["a.deb", "b.deb", "c.deb"] {
+        let content = Content::new(
+            "my-company", "my-repo", "my-package", "1.0.0", name);
+        assert_eq!(content.get_repository_type(&client).unwrap(),
+                   RepositoryType::Debian);
+    }
+    assert_eq!(transport.get_requests().len(), 1);
+
+    client.forget_repository("my-company", "my-repo");
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "d.deb");
+    assert!(content.get_repository_type(&client).is_ok());
+    assert_eq!(transport.get_requests().len(), 2);
+
+    client.clear_repository_cache();
+    assert!(content.get_repository_type(&client).is_ok());
+    assert_eq!(transport.get_requests().len(), 3);
+}
+
+#[test]
+fn get_repository_fresh() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json("generic", "")))
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, &repository_json("rpm", "")));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    assert_eq!(client.get_repository("my-company", "my-repo").unwrap().type_,
+               RepositoryType::Generic);
+    assert_eq!(client.get_repository("my-company", "my-repo").unwrap().type_,
+               RepositoryType::Generic);
+    assert_eq!(transport.get_requests().len(), 1);
+
+    // The repository was recreated with another type by someone else.
+    assert_eq!(client.get_repository_fresh("my-company", "my-repo")
+                   .unwrap().type_,
+               RepositoryType::Rpm);
+    assert_eq!(transport.get_requests().len(), 2);
+    assert_eq!(client.get_repository_type("my-company", "my-repo").unwrap(),
+               RepositoryType::Rpm);
+    assert_eq!(transport.get_requests().len(), 2);
+}
+
+#[test]
+fn repository_type_only() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "name": "my-repo", "type": "rpm",
+                "some_new_attribute": true
+            }"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    assert_eq!(repository.get_type(&client).unwrap(), RepositoryType::Rpm);
+    assert!(repository.get(&client).is_err());
+}
+
+#[test]
+fn upload_cancelled() {
+    let transport = MockTransport::new()
+        .on(Method::Put, "/content/my-company/my-repo/my-package/1.0.0/hello.txt",
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_cancelled");
+    let local_filename = test_dir.join("upload-cancelled");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt");
+    let no_values: &[&str] = &[];
+    let cancel = AtomicBool::new(false);
+    assert!(content.upload_cancellable(
+            &local_filename, false, false, false, None,
+            no_values, no_values, no_values, &cancel, &client).is_ok());
+
+    cancel.store(true, Ordering::SeqCst);
+    assert!(content.upload_cancellable(
+            &local_filename, false, false, false, None,
+            no_values, no_values, no_values, &cancel, &client).is_err());
+    assert_eq!(transport.get_requests().len(), 1);
+}
+
+#[test]
+fn upload_or_override() {
+    let path = "/content/my-company/my-repo/my-package/1.0.0/hello.txt";
+    let transport = MockTransport::new()
+        .on(Method::Put, path,
+            MockResponse::new(409, r#"{"message": "File exists"}"#))
+        .on(Method::Put, path,
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_or_override");
+    let local_filename = test_dir.join("upload-or-override");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt");
+    let no_values: &[&str] = &[];
+    match content.upload(&local_filename, false, false, false, None,
+                         no_values, no_values, no_values, &client) {
+        Err(BintrayError::Conflict { message, .. }) => {
+            assert_eq!(message, "File exists")
+        }
+        _ => panic!("Expected a Conflict error"),
+    }
+
+    let transport = MockTransport::new()
+        .on(Method::Put, path,
+            MockResponse::new(409, r#"{"message": "File exists"}"#))
+        .on(Method::Put, path,
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+    assert!(content.upload_or_override(
+            &local_filename, false, false, None,
+            no_values, no_values, no_values, &client).is_ok());
+
+    let paths: Vec<String> = transport.get_requests().into_iter()
+        .map(|request| request.path)
+        .collect();
+    assert_eq!(paths, vec![String::from(path), format!("{}?override=1", path)]);
+}
+
+#[test]
+fn upload_with_checksum_headers() {
+    let path = "/content/my-company/my-repo/my-package/1.0.0/hello.txt";
+    let transport = MockTransport::new()
+        .on(Method::Put, path,
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_with_checksum_headers");
+    let local_filename = test_dir.join("checksum-headers");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let no_values: &[&str] = &[];
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt")
+        .set_checksum_header_algorithm(ChecksumAlgorithm::Sha1);
+    assert!(content.upload(&local_filename, false, false, false, None,
+                           no_values, no_values, no_values, &client)
+            .is_err());
+    assert!(transport.get_requests().is_empty());
+
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt")
+        .set_checksum_header_algorithm(ChecksumAlgorithm::Both);
+    content.set_checksum_from_file(&local_filename).unwrap();
+    assert!(content.upload(&local_filename, false, false, false, None,
+                           no_values, no_values, no_values, &client)
+            .is_ok());
+
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].get_header("X-Checksum-Sha1"),
+               Some("f572d396fae9206628714fb2ce00f72e94f2258f"));
+    assert_eq!(
+        requests[0].get_header("X-Checksum-Sha2"),
+        Some("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"));
+}
+
+#[test]
+fn accessible_subjects() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/users/john",
+            MockResponse::new(200, r#"{
+                "name": "john",
+                "organizations": ["my-company", "john", "another-company"]
+            }"#));
+    let client = BintrayClient::mock(
+        Some(String::from("john")), Some(String::from("api-key")),
+        transport.clone());
+    assert_eq!(client.accessible_subjects().unwrap(),
+               vec!["another-company", "john", "my-company"]);
+
+    let client = BintrayClient::mock(None, None, transport.clone());
+    assert!(client.accessible_subjects().unwrap().is_empty());
+    assert_eq!(transport.get_requests().len(), 1);
+}
+
+#[test]
+fn verify_manifest() {
+    let files = files_json(&[
+        FileJson::new("1.0.0", "a.txt"),
+        FileJson::new("1.0.0", "b.txt"),
+        FileJson::new("1.0.0", "c.txt"),
+    ]);
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo/packages",
+            MockResponse::new(200, r#"[{"name": "my-package"}]"#))
+        .on(Method::Get, "/packages/my-company/my-repo/my-package/files",
+            MockResponse::new(200, &files))
+        .on(Method::Head, "/my-company/my-repo/a.txt",
+            MockResponse::new(200, "")
+            .header("X-Checksum-Sha2", "0102ff"))
+        .on(Method::Head, "/my-company/my-repo/b.txt",
+            MockResponse::new(200, "")
+            .header("X-Checksum-Sha2", "0102fe"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let repository = Repository::new("my-company", "my-repo");
+    let manifest = vec![
+        (PathBuf::from("a.txt"), vec![0x01, 0x02, 0xff]),
+        (PathBuf::from("b.txt"), vec![0x01, 0x02, 0xff]),
+        (PathBuf::from("d.txt"), vec![0x01, 0x02, 0xff]),
+    ];
+    let report = repository.verify_manifest(&manifest, &client).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.missing, vec![PathBuf::from("d.txt")]);
+    assert_eq!(report.extra, vec![PathBuf::from("c.txt")]);
+    assert_eq!(report.mismatched, vec![PathBuf::from("b.txt")]);
+}
+
+#[test]
+fn download_all_files_of_version() {
+    let files = files_json(&[
+        FileJson::new("1.0.0", "a.txt")
+            .set_size(2)
+            .set_sha1("3f786850e387550fdab836ed7e6dc881de23001b"),
+        FileJson::new("1.0.0", "sub/dir/b.txt")
+            .set_size(2)
+            .set_sha1("89e6c98d92887913cadf06b2adb97f26cde4849b"),
+    ]);
+    let transport = MockTransport::new()
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0/files",
+            MockResponse::new(200, &files))
+        .on(Method::Get, "/my-company/my-repo/a.txt",
+            MockResponse::new(200, "a\n"))
+        .on(Method::Get, "/my-company/my-repo/sub/dir/b.txt",
+            MockResponse::new(200, "b\n"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("download_all_files_of_version");
+    let dest_dir = test_dir.join("download-all");
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    let local_filenames = version.download_all(&dest_dir, 2, &client).unwrap();
+    assert_eq!(local_filenames, vec![dest_dir.join("a.txt"),
+                                     dest_dir.join("sub/dir/b.txt")]);
+    assert_eq!(fs::read_to_string(dest_dir.join("sub/dir/b.txt")).unwrap(),
+               "b\n");
+}
+
+#[test]
+fn unauthorized_vs_invalid_credentials() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(401, r#"{"message": "This resource requires authentication"}"#));
+
+    let client = BintrayClient::mock(None, None, transport.clone());
+    let mut repository = Repository::new("my-company", "my-repo");
+    match repository.get(&client) {
+        Err(BintrayError::Unauthorized { message, .. }) => {
+            assert_eq!(message, "This resource requires authentication")
+        }
+        _ => panic!("Expected an Unauthorized error"),
+    }
+
+    let client = BintrayClient::mock(
+        Some(String::from("john")), Some(String::from("bad-key")),
+        transport.clone());
+    match repository.get(&client) {
+        Err(BintrayError::InvalidCredentials { .. }) => { }
+        _ => panic!("Expected an InvalidCredentials error"),
+    }
+}
+
+#[test]
+fn maven_path() {
+    assert_eq!(RepositoryType::maven_path(
+            "io.pivotal.bintray_rs", "my-lib", "1.0.0", "my-lib-1.0.0.jar"),
+            PathBuf::from("io/pivotal/bintray_rs/my-lib/1.0.0/my-lib-1.0.0.jar"));
+    assert_eq!(RepositoryType::maven_path(
+            "example", "my-lib", "1.0.0", "my-lib-1.0.0.pom"),
+            PathBuf::from("example/my-lib/1.0.0/my-lib-1.0.0.pom"));
+}
+
+#[test]
+fn content_upload_path() {
+    let path = RepositoryType::maven_path(
+        "io.pivotal.bintray_rs", "my-lib", "1.0.0", "my-lib-1.0.0.jar");
+    assert_eq!(RepositoryType::Maven.content_upload_path(
+            "my-company", "my-repo", "my-lib", "1.0.0", &path),
+            "maven/my-company/my-repo/my-lib/\
+             io/pivotal/bintray_rs/my-lib/1.0.0/my-lib-1.0.0.jar");
+    assert_eq!(RepositoryType::Generic.content_upload_path(
+            "my-company", "my-repo", "my-package", "1.0.0", "dir/file.txt"),
+            "content/my-company/my-repo/my-package/1.0.0/dir/file.txt");
+    assert_eq!(RepositoryType::Debian.content_upload_path(
+            "my-company", "my-repo", "my-package", "1.0.0", "file.deb"),
+            "content/my-company/my-repo/my-package/1.0.0/file.deb");
+    assert_eq!(RepositoryType::Generic.content_upload_segments(
+            "my-company", "my-repo", "my-package", "1.0.0/rc", "/dir/file.txt"),
+            vec!["content", "my-company", "my-repo", "my-package", "1.0.0/rc",
+                 "dir", "file.txt"]);
+
+    // A "/" in a name stays in its segment and is percent-encoded.
+    let transport = MockTransport::new()
+        .on(Method::Put,
+            "/content/my-company/my-repo/my-package/1.0.0%2Frc/dir/file.txt",
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("content_upload_path");
+    let local_filename = test_dir.join("file.txt");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0/rc", "dir/file.txt");
+    assert!(content.upload_with_options(&local_filename, &UploadOptions::new(), &client)
+            .is_ok());
+    assert_eq!(transport.get_requests().len(), 1);
+}
+
+#[test]
+fn upload_maven() {
+    let base = "/maven/my-company/my-maven-repo/my-lib/io/pivotal/bintray_rs";
+    let transport = MockTransport::new()
+        .on(Method::Put, &format!("{}/my-lib/1.0.0/my-lib-1.0.0.jar;publish=1", base),
+            MockResponse::new(201, r#"{"message": "success"}"#))
+        .on(Method::Put, &format!("{}/my-lib/1.0.0/my-lib-1.0.0-sources.jar", base),
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_maven");
+    let local_filename = test_dir.join("upload-maven.jar");
+    fs::write(&local_filename, "PK").unwrap();
+
+    let version = Version::new(
+        "my-company", "my-maven-repo", "my-lib", "1.0.0");
+    assert!(version.upload_maven(
+            "io.pivotal.bintray_rs", "my-lib", None, &local_filename, true,
+            &client).is_ok());
+    assert!(version.upload_maven(
+            "io.pivotal.bintray_rs", "my-lib", Some("sources"),
+            &local_filename, false, &client).is_ok());
+    assert_eq!(transport.get_requests().len(), 2);
+}
+
+#[test]
+fn licenses() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/licenses/oss_licenses",
+            MockResponse::new(200, r#"[
+                {"name": "Apache-2.0", "longname": "Apache License 2.0",
+                 "url": "http://www.apache.org/licenses/LICENSE-2.0"},
+                {"name": "MIT", "longname": "The MIT License",
+                 "url": "http://opensource.org/licenses/MIT"}
+            ]"#))
+        .on(Method::Get, "/users/my-company",
+            MockResponse::new(404, r#"{"message": "User not found"}"#))
+        .on(Method::Get, "/orgs/my-company",
+            MockResponse::new(200, r#"{"name": "my-company"}"#))
+        .on(Method::Get, "/orgs/my-company/licenses",
+            MockResponse::new(200, r#"[
+                {"name": "My-License", "description": "", "url": ""}
+            ]"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    assert_eq!(client.oss_licenses().unwrap(), vec!["Apache-2.0", "MIT"]);
+    assert!(client.is_valid_license("MIT").unwrap());
+    assert!(!client.is_valid_license("WTFPL").unwrap());
+    assert_eq!(transport.get_requests().len(), 1);
+
+    assert_eq!(client.proprietary_licenses("my-company").unwrap(),
+               vec!["My-License"]);
+    let requests = transport.get_requests().len();
+    assert_eq!(client.proprietary_licenses("my-company").unwrap(),
+               vec!["My-License"]);
+    assert_eq!(transport.get_requests().len(), requests);
+}
+
+#[test]
+fn checksum_buffer_size() {
+    let data: Vec<u8> = (0..1_000_003u32).map(|i| (i % 251) as u8).collect();
+
+    let mut default = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "data.bin");
+    default.set_checksum_from_reader(&data[..]).unwrap();
+
+    for &size in &[1, 1000, 1 << 20] {
+        let mut content = Content::new(
+            "my-company", "my-repo", "my-package", "1.0.0", "data.bin")
+            .set_checksum_buffer_size(size);
+        content.set_checksum_from_reader(&data[..]).unwrap();
+        assert_eq!(content.sha1, default.sha1);
+        assert_eq!(content.sha256, default.sha256);
+    }
+}
+
+#[test]
+fn upload_with_version_attributes() {
+    let version_path = "/packages/my-company/my-repo/my-package/versions/1.0.0";
+    let path = "/content/my-company/my-repo/my-package/1.0.0/hello.txt";
+    let transport = MockTransport::new()
+        .on(Method::Get, version_path,
+            MockResponse::new(404, r#"{"message": "Version not found"}"#))
+        .on(Method::Get, version_path,
+            MockResponse::new(200, &version_json("1.0.0", "")))
+        .on(Method::Put, path,
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_with_version_attributes");
+    let local_filename = test_dir.join("version-attributes");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let no_values: &[&str] = &[];
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt")
+        .set_version_attribute("commit", "0123abcd")
+        .set_version_attribute("platform", "linux")
+        .set_version_attribute("platform", "macos");
+    assert!(content.upload(&local_filename, false, false, false, None,
+                           no_values, no_values, no_values, &client)
+            .is_ok());
+
+    // The version doesn't exist yet: the upload creates it and sets its
+    // attributes.
+    let requests = transport.get_requests();
+    assert_eq!(requests[0].method, Method::Get);
+    assert_eq!(requests[1].method, Method::Put);
+    assert_eq!(
+        requests[1].get_header("X-Bintray-Version-Attributes"),
+        Some(r#"[{"name":"commit","type":"string","values":["0123abcd"]},{"name":"platform","type":"string","values":["linux","macos"]}]"#));
+
+    // The version exists: its attributes are left untouched.
+    assert!(content.upload(&local_filename, false, true, false, None,
+                           no_values, no_values, no_values, &client)
+            .is_ok());
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[3].method, Method::Put);
+    assert_eq!(requests[3].get_header("X-Bintray-Version-Attributes"), None);
+}
+
+#[test]
+fn checksum_from_file() {
+    let test_dir = TestDir::new("checksum_from_file");
+    let local_filename = test_dir.join("checksum-from-file");
+
+    fs::write(&local_filename, "").unwrap();
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "empty.txt");
+    content.set_checksum_from_file(&local_filename).unwrap();
+    assert_eq!(content.sha1.as_ref().unwrap(),
+               "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(
+        content.sha256.as_ref().unwrap(),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+    fs::write(&local_filename, "hello\n").unwrap();
+    let mut content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt")
+        .set_checksum_buffer_size(4);
+    content.set_checksum_from_file(&local_filename).unwrap();
+    assert_eq!(content.sha1.as_ref().unwrap(),
+               "f572d396fae9206628714fb2ce00f72e94f2258f");
+    assert_eq!(
+        content.sha256.as_ref().unwrap(),
+        "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03");
+}
+
+#[test]
+fn set_repository_privacy() {
+    let transport = MockTransport::new()
+        .on(Method::Patch, "/repos/my-company/my-repo",
+            MockResponse::new(200, "{}"))
+        .on(Method::Patch, "/repos/my-company/my-repo",
+            MockResponse::new(400, r#"{"message": "Premium account required"}"#))
+        .on(Method::Patch, "/repos/my-company/my-repo",
+            MockResponse::new(400, r#"{"message": "Invalid JSON"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    assert!(repository.set_privacy(true, &client).is_ok());
+    assert!(repository.private);
+
+    let requests = transport.get_requests();
+    assert_eq!(String::from_utf8_lossy(&requests[0].body)
+               .split_whitespace().collect::<String>(),
+               r#"{"private":true}"#);
+
+    match repository.set_privacy(true, &client) {
+        Err(BintrayError::Io(ref e))
+            if e.kind() == std::io::ErrorKind::PermissionDenied => { }
+        _ => panic!("Expected a PermissionDenied error"),
+    }
+
+    // Other refusals are not mistaken for the premium restriction.
+    match repository.set_privacy(true, &client) {
+        Err(BintrayError::Io(ref e))
+            if e.kind() == std::io::ErrorKind::Other => { }
+        _ => panic!("Expected an unrecognized error"),
+    }
+}
+
+#[test]
+fn signing_report() {
+    let report = |repository: String, files: &[FileJson],
+                  signatures: &[&str]| {
+        let mut transport = MockTransport::new()
+            .on(Method::Get, "/repos/my-company/my-repo",
+                MockResponse::new(200, &repository))
+            .on(Method::Get, "/repos/my-company/my-repo/packages",
+                MockResponse::new(200, r#"[{"name": "my-package"}]"#))
+            .on(Method::Get, "/packages/my-company/my-repo/my-package",
+                MockResponse::new(200, &package_json("my-package", r#"
+                    "versions": ["1.2.0", "1.1.0", "1.0.0"],
+                    "latest_version": "1.2.0""#)))
+            .on(Method::Get, "/packages/my-company/my-repo/my-package/files",
+                MockResponse::new(200, &files_json(files)));
+        for signature in signatures {
+            transport = transport.on(Method::Head, signature,
+                                     MockResponse::new(200, ""));
+        }
+        let client = BintrayClient::mock(None, None, transport.clone());
+
+        let repository = Repository::new("my-company", "my-repo");
+        let signed: Vec<bool> = repository.signing_report(&client)
+            .unwrap()
+            .into_iter()
+            .map(|(package, _, signed)| {
+                assert_eq!(package, "my-package");
+                signed
+            })
+            .collect();
+        let mut probed: Vec<String> = transport.get_requests().into_iter()
+            .filter(|request| request.method == Method::Head)
+            .map(|request| request.path)
+            .collect();
+        probed.sort();
+        (signed, probed)
+    };
+
+    // Generic repositories only have per-file signatures.
+    let (signed, probed) = report(repository_json("generic", ""), &[
+        FileJson::new("1.0.0", "a.deb"), FileJson::new("1.0.0", "a.deb.asc"),
+        FileJson::new("1.0.0", "b.deb"), FileJson::new("1.0.0", "b.deb.asc"),
+        FileJson::new("1.1.0", "a.deb"), FileJson::new("1.1.0", "a.deb.asc"),
+        FileJson::new("1.1.0", "b.deb"),
+    ], &[]);
+    assert_eq!(signed, vec![true, false, false]);
+    assert!(probed.is_empty());
+
+    // Debian repositories are signed by their Release file, whatever
+    // the per-file signatures.
+    let debian = repository_json("deb", r#"
+        "default_debian_distribution": "stretch",
+        "default_debian_component": "main",
+        "default_debian_architecture": "amd64""#);
+    let debian_files = [
+        FileJson::new("1.0.0", "pool/a_1.0.0.deb"),
+        FileJson::new("1.1.0", "pool/a_1.1.0.deb"),
+    ];
+    let (signed, probed) = report(
+        debian.clone(), &debian_files,
+        &["/my-company/my-repo/dists/stretch/Release.gpg"]);
+    assert_eq!(signed, vec![true, true, false]);
+    assert_eq!(probed, vec!["/my-company/my-repo/dists/stretch/InRelease",
+                            "/my-company/my-repo/dists/stretch/Release.gpg"]);
+    let (signed, _) = report(
+        debian.clone(), &debian_files,
+        &["/my-company/my-repo/dists/stretch/InRelease"]);
+    assert_eq!(signed, vec![true, true, false]);
+    let (signed, _) = report(debian, &debian_files, &[]);
+    assert_eq!(signed, vec![false, false, false]);
+
+    // RPM repositories are signed by each repomd.xml file.
+    let (signed, probed) = report(
+        repository_json("rpm", r#""yum_metadata_depth": 1"#), &[
+            FileJson::new("1.0.0", "1.0.0/a.rpm"),
+            FileJson::new("1.1.0", "1.1.0/a.rpm"),
+            FileJson::new("1.1.0", "1.1.0/b.rpm"),
+        ], &["/my-company/my-repo/1.0.0/repodata/repomd.xml.asc"]);
+    assert_eq!(signed, vec![true, false, false]);
+    assert_eq!(probed,
+               vec!["/my-company/my-repo/1.0.0/repodata/repomd.xml.asc",
+                    "/my-company/my-repo/1.1.0/repodata/repomd.xml.asc"]);
+}
+
+#[test]
+fn vcs_tag_url() {
+    let url = |vcs_url| {
+        utils::vcs_tag_url(vcs_url, "v1.0.0").map(|url| url.into_string())
+    };
+    assert_eq!(url("https://github.com/my-company/my-project.git"),
+               Some(String::from(
+                       "https://github.com/my-company/my-project/tree/v1.0.0")));
+    assert_eq!(url("git@github.com:my-company/my-project.git"),
+               Some(String::from(
+                       "https://github.com/my-company/my-project/tree/v1.0.0")));
+    assert_eq!(url("https://gitlab.com/my-company/group/my-project"),
+               Some(String::from(
+                       "https://gitlab.com/my-company/group/my-project/-/tags/v1.0.0")));
+    assert_eq!(url("https://bitbucket.org/my-company/my-project/"),
+               Some(String::from(
+                       "https://bitbucket.org/my-company/my-project/src/v1.0.0")));
+    assert_eq!(url("https://git.example.com/my-project.git"), None);
+    assert_eq!(url("not a URL"), None);
+}
+
+#[test]
+fn version_source_url() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/packages/my-company/my-repo/my-package",
+            MockResponse::new(200, r#"{
+                "owner": "my-company", "repo": "my-repo",
+                "name": "my-package",
+                "public_download_numbers": false, "public_stats": false,
+                "versions": ["1.0.0"], "latest_version": "1.0.0",
+                "vcs_url": "https://github.com/my-company/my-package.git"
+            }"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    assert_eq!(version.source_url(&client).unwrap(), None);
+    assert!(transport.get_requests().is_empty());
+
+    version.vcs_tag = Some(String::from("v1.0.0"));
+    assert_eq!(version.source_url(&client).unwrap().unwrap().as_str(),
+               "https://github.com/my-company/my-package/tree/v1.0.0");
+}
+
+#[test]
+fn download_many() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/my-company/my-repo/a.txt",
+            MockResponse::new(200, "a\n"))
+        .on(Method::Get, "/my-company/my-repo/sub/b.txt",
+            MockResponse::new(503, "{}"))
+        .on(Method::Get, "/my-company/my-repo/sub/b.txt",
+            MockResponse::new(200, "b\n"))
+        .on(Method::Get, "/my-company/my-repo/c.txt",
+            MockResponse::new(404, "{}"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let dest_dir = TestDir::new("download_many");
+
+    let files: Vec<Content> = ["a.txt", "sub/b.txt", "c.txt"].iter()
+        .map(|path| {
+            Content::new("my-company", "my-repo", "my-package", "1.0.0", path)
+        })
+        .collect();
+    let calls = AtomicUsize::new(0);
+    let report = content::download_many(
+        &files, &dest_dir, 2, 1,
+        |done, total| {
+            assert_eq!(total, 3);
+            assert!(done <= total);
+            calls.fetch_add(1, Ordering::SeqCst);
+        },
+        &client);
+
+    assert_eq!(report.downloaded, vec![dest_dir.join("a.txt"),
+                                       dest_dir.join("sub/b.txt")]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0.path, PathBuf::from("c.txt"));
+    assert!(match report.failed[0].1 {
+        BintrayError::Io(ref e) => e.kind() == io::ErrorKind::NotFound,
+        _ => false,
+    });
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    // sub/b.txt is retried after the 503, c.txt is not retried after
+    // the 404.
+    let mut paths: Vec<String> = transport.get_requests().into_iter()
+        .map(|request| request.path)
+        .collect();
+    paths.sort();
+    assert_eq!(paths, vec!["/my-company/my-repo/a.txt",
+                           "/my-company/my-repo/c.txt",
+                           "/my-company/my-repo/sub/b.txt",
+                           "/my-company/my-repo/sub/b.txt"]);
+}
+
+#[test]
+fn download_many_rejects_unsafe_paths() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/my-company/my-repo/a.txt",
+            MockResponse::new(200, "a\n"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let dest_dir = TestDir::new("download_many_rejects_unsafe_paths");
+    let mut escaping = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "a.txt");
+    escaping.path = PathBuf::from("sub/../../a.txt");
+    let files = vec![escaping];
+
+    let report = content::download_many(
+        &files, &dest_dir, 1, 0, |_, _| { }, &client);
+    assert!(report.downloaded.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert!(match report.failed[0].1 {
+        BintrayError::UnsafePath { ref path } => {
+            path == &PathBuf::from("sub/../../a.txt")
+        }
+        _ => false,
+    });
+    assert!(transport.get_requests().is_empty());
+}
+
+#[test]
+fn install_gpg_key() {
+    let key = "-----BEGIN PGP PUBLIC KEY BLOCK-----\n\
+               \n\
+               mQENBFjJ8tYBCAC7\n\
+               -----END PGP PUBLIC KEY BLOCK-----\n";
+    let transport = MockTransport::new()
+        .on(Method::Get, "/users/my-company",
+            MockResponse::new(200, r#"{"name": "my-company"}"#))
+        .on(Method::Get, "/users/my-company/keys/gpg/public.key",
+            MockResponse::new(200, key))
+        .on(Method::Get, "/users/my-company/keys/gpg/public.key",
+            MockResponse::new(200, "<html>Not a key</html>"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("install_gpg_key");
+    let path = test_dir.join("install-gpg-key.asc");
+
+    let repository = Repository::new("my-company", "my-repo");
+    assert_eq!(repository.install_gpg_key(&path, &client).unwrap(), key.len());
+    assert_eq!(fs::read_to_string(&path).unwrap(), key);
+    fs::remove_file(&path).unwrap();
+
+    assert!(repository.install_gpg_key(&path, &client).is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn check_files() {
+    let transport = MockTransport::new()
+        .on(Method::Head, "/my-company/my-repo/a.txt",
+            MockResponse::new(200, "").header("X-Checksum-Sha2", "AAAA"))
+        .on(Method::Head, "/my-company/my-repo/b.txt",
+            MockResponse::new(200, "").header("X-Checksum-Sha2", "bbbb"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let files: Vec<Content> = ["a.txt", "b.txt", "c.txt"].iter()
+        .map(|path| {
+            let mut file = Content::new(
+                "my-company", "my-repo", "my-package", "1.0.0", path);
+            file.sha256 = Some(String::from("aaaa"));
+            file
+        })
+        .collect();
+    assert_eq!(client.check_files(&files, 2).unwrap(), vec![
+        (PathBuf::from("a.txt"), FileStatus::Present { matches_checksum: true }),
+        (PathBuf::from("b.txt"), FileStatus::Present { matches_checksum: false }),
+        (PathBuf::from("c.txt"), FileStatus::Absent),
+    ]);
+}
+
+#[test]
+fn stale_unpublished_files() {
+    let published = files_json(&[FileJson::new("1.0.0", "a.txt")]);
+    let all = files_json(&[
+        FileJson::new("1.0.0", "a.txt"),
+        FileJson::new("1.0.0", "b.txt"),
+        FileJson::new("1.0.0", "c.txt")
+            .set_created("2999-03-01T12:34:56.789Z"),
+        FileJson::new("1.0.0", "d.txt")
+            .set_created("2017-03-01T13:34:56+01:00"),
+        FileJson::new("1.0.0", "e.txt")
+            .set_created("2999-03-01T12:34:56-05:00"),
+        FileJson::new("1.0.0", "f.txt").set_created("1st of March"),
+    ]);
+
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo/packages",
+            MockResponse::new(200, r#"[{"name": "my-package"}]"#))
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/files?include_unpublished=1",
+            MockResponse::new(200, &all))
+        .on(Method::Get, "/packages/my-company/my-repo/my-package/files",
+            MockResponse::new(200, &published));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let repository = Repository::new("my-company", "my-repo");
+    let stale = repository.stale_unpublished_files(
+        Duration::from_secs(86400), &client).unwrap();
+    let paths: Vec<PathBuf> = stale.into_iter()
+        .map(|file| file.path)
+        .collect();
+    assert_eq!(paths, vec![PathBuf::from("b.txt"), PathBuf::from("d.txt")]);
+}
+
+#[test]
+fn download_with_identity_encoding() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/my-company/my-repo/pool/a.deb",
+            MockResponse::new(200, "PK"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("download_with_identity_encoding");
+    let local_filename = test_dir.join("identity-encoding.deb");
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "pool/a.deb");
+    assert!(content.download(&local_filename, &client).is_ok());
+
+    let requests = transport.get_requests();
+    assert_eq!(requests[0].get_header("Accept-Encoding"), Some("identity"));
+}
+
+#[test]
+fn upload_with_options() {
+    let path = "/content/my-company/my-repo/my-package/1.0.0/hello.txt";
+    let transport = MockTransport::new()
+        .on(Method::Put, path,
+            MockResponse::new(201, r#"{"message": "success"}"#))
+        .on(Method::Put, "/file_metadata/my-company/my-repo/hello.txt",
+            MockResponse::new(200, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_with_options");
+    let local_filename = test_dir.join("upload-options");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let options = UploadOptions {
+        publish: true,
+        show_in_download_list: true,
+        ..UploadOptions::default()
+    };
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "hello.txt");
+    assert!(content.upload_with_options(&local_filename, &options, &client)
+            .is_ok());
+
+    let paths: Vec<String> = transport.get_requests().into_iter()
+        .map(|request| request.path)
+        .collect();
+    assert_eq!(paths, vec![format!("{}?publish=1", path),
+                           String::from("/file_metadata/my-company/my-repo/hello.txt")]);
+}
+
+// Files in /proc report a size of 0 but aren't empty, like a file
+// growing while it is uploaded.
+#[cfg(target_os = "linux")]
+#[test]
+fn upload_size_mismatch() {
+    let path = "/content/my-company/my-repo/my-package/1.0.0/status";
+    let transport = MockTransport::new()
+        .on(Method::Put, path, MockResponse::new(201, "{}"));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let no_values: &[&str] = &[];
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "status");
+    match content.upload(&PathBuf::from("/proc/self/status"),
+                         false, false, false, None,
+                         no_values, no_values, no_values, &client) {
+        Err(BintrayError::UploadSizeMismatch { declared, actual }) => {
+            assert_eq!(declared, 0);
+            assert!(actual > 0);
+        }
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn upload_with_result() {
+    let path = "/content/my-company/my-repo/my-package/1.0.0/dir/hello.txt";
+    let transport = MockTransport::new()
+        .on(Method::Put, &format!("{}?publish=1", path),
+            MockResponse::new(201, r#"{"warn": "Version is already published"}"#))
+        .on(Method::Put, &format!("{}?override=1", path),
+            MockResponse::new(409, r#"{"message": "File unchanged"}"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let test_dir = TestDir::new("upload_with_result");
+    let local_filename = test_dir.join("upload-result");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let no_values: &[&str] = &[];
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "dir/hello.txt");
+    assert_eq!(content.upload_with_result(
+            &local_filename, true, false, false, None,
+            no_values, no_values, no_values, &client).unwrap(),
+        UploadResult {
+            path: PathBuf::from("dir/hello.txt"),
+            uploaded: true,
+            size: 6,
+            published: true,
+            warning: Some(String::from("Version is already published")),
+        });
+
+    let content = content.set_override_if_different(true);
+    let result = content.upload_with_result(
+        &local_filename, false, false, false, None,
+        no_values, no_values, no_values, &client).unwrap();
+    assert!(!result.uploaded);
+    assert_eq!(result.size, 0);
+}
+
+#[test]
+fn empty_error_message() {
+    let transport = MockTransport::new()
+        .on(Method::Delete, "/packages/my-company/my-repo/my-package",
+            MockResponse::new(403, r#"{"message": ""}"#))
+        .on(Method::Delete, "/packages/my-company/my-repo/my-package",
+            MockResponse::new(403, r#"{"message": "Not your package"}"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let package = Package::new("my-company", "my-repo", "my-package");
+    let error = package.delete(&client).unwrap_err().to_string();
+    assert!(error.contains(": Forbidden (403 Forbidden)"), "{}", error);
+    let error = package.delete(&client).unwrap_err().to_string();
+    assert!(error.contains(": Not your package (403 Forbidden)"), "{}", error);
+}
+
+#[test]
+fn content_is_published() {
+    let files = "/packages/my-company/my-repo/my-package/versions/1.0.0/files";
+    let transport = MockTransport::new()
+        .on(Method::Get, &format!("{}?include_unpublished=1", files),
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "published.txt"),
+                FileJson::new("1.0.0", "draft.txt"),
+            ])))
+        .on(Method::Get, files,
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "published.txt"),
+            ])));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let content = |path: &str| {
+        Content::new("my-company", "my-repo", "my-package", "1.0.0", path)
+    };
+    assert!(content("/published.txt").is_published(&client).unwrap());
+    assert!(!content("draft.txt").is_published(&client).unwrap());
+    match content("missing.txt").is_published(&client) {
+        Err(BintrayError::Io(ref error)) => {
+            assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        }
+        result => panic!("Expected a NotFound error, got {:?}", result),
+    }
+
+    let transport = MockTransport::new()
+        .on(Method::Get, &format!("{}?include_unpublished=1", files),
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "draft.txt"),
+            ])));
+    let client = BintrayClient::mock(None, None, transport);
+
+    assert_eq!(content("draft.txt").uploaded_at(&client).unwrap().unwrap(),
+               "2017-03-01T12:34:56.789Z");
+    assert!(content("missing.txt").uploaded_at(&client).is_err());
+}
+
+#[test]
+fn version_publication_counts() {
+    let files = "/packages/my-company/my-repo/my-package/versions/1.0.0/files";
+    let transport = MockTransport::new()
+        .on(Method::Get, &format!("{}?include_unpublished=1", files),
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "a.txt"),
+                FileJson::new("1.0.0", "b.txt"),
+                FileJson::new("1.0.0", "c.txt"),
+            ])))
+        .on(Method::Get, files,
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "a.txt"),
+            ])))
+        .on(Method::Get, "/packages/my-company/my-repo/my-package/versions/2.0.0/files",
+            MockResponse::new(404, r#"{"message": "Version not found"}"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    assert_eq!(version.publication_counts(&client).unwrap(), (1, 2));
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "2.0.0");
+    assert!(version.publication_counts(&client).is_err());
+}
+
+#[test]
+fn upload_and_publish() {
+    let transport = MockTransport::new()
+        .on(Method::Put,
+            "/content/my-company/my-repo/my-package/1.0.0/pool/my-package.deb",
+            MockResponse::new(201, r#"{"message": "success"}"#))
+        .on(Method::Post,
+            "/content/my-company/my-repo/my-package/1.0.0/publish",
+            MockResponse::new(200, r#"{"files": 1, "warn": "Slow indexing"}"#))
+        .on(Method::Head, "/my-company/my-repo/pool/my-package.deb",
+            MockResponse::new(200, ""));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("upload_and_publish");
+    let local_filename = test_dir.join("upload-and-publish");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let options = PublishOptions {
+        upload: UploadOptions::new().set_debian_distribution(&["stretch"]),
+        repository_type: Some(RepositoryType::Debian),
+        checksum_header_algorithm: Some(ChecksumAlgorithm::Sha256),
+        availability_timeout: Some(Duration::from_secs(10)),
+        ..PublishOptions::default()
+    };
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    assert_eq!(version.upload_and_publish(
+            &local_filename, "pool/my-package.deb", &options, &client)
+               .unwrap(),
+               vec![String::from("Slow indexing")]);
+
+    let requests = transport.get_requests();
+    let methods: Vec<Method> = requests.iter()
+        .map(|request| request.method.clone())
+        .collect();
+    assert_eq!(methods, vec![Method::Put, Method::Post, Method::Head]);
+    assert_eq!(requests[0].get_header("X-Bintray-Debian-Distribution"),
+               Some("stretch"));
+    assert_eq!(requests[0].get_header("X-Checksum-Sha2"),
+               Some("5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"));
+}
+
+#[test]
+fn client_last_response_headers() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(404, r#"{"message": "Repo not found"}"#)
+            .header("X-Request-Id", "42"));
+    let client = BintrayClient::mock(None, None, transport);
+    assert!(client.last_response_headers().is_none());
+
+    let mut repository = Repository::new("my-company", "my-repo");
+    assert!(repository.get(&client).is_err());
+    let headers = client.last_response_headers().unwrap();
+    assert_eq!(utils::get_raw_header(&headers, "X-Request-Id").unwrap(), "42");
+}
+
+#[test]
+fn client_correlation_id() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/repos/my-company/my-repo",
+            MockResponse::new(404, r#"{"message": "Repo not found"}"#));
+    let mut repository = Repository::new("my-company", "my-repo");
+
+    let client = BintrayClient::mock(None, None, transport.clone());
+    assert!(repository.get(&client).is_err());
+    let client = BintrayClient::mock(None, None, transport.clone())
+        .set_correlation_id("build-42").unwrap();
+    assert!(repository.get(&client).is_err());
+    let client = BintrayClient::mock(None, None, transport.clone())
+        .set_correlation_id("build-43").unwrap()
+        .set_correlation_header("X-Correlation-Id").unwrap();
+    assert!(repository.get(&client).is_err());
+
+    let requests = transport.get_requests();
+    assert!(requests[0].get_header("X-Request-Id").is_none());
+    assert_eq!(requests[1].get_header("X-Request-Id"), Some("build-42"));
+    assert!(requests[2].get_header("X-Request-Id").is_none());
+    assert_eq!(requests[2].get_header("X-Correlation-Id"), Some("build-43"));
+
+    // Headers can't be injected.
+    let client = || BintrayClient::mock(None, None, transport.clone());
+    for id in &["build-44\r\nX-Injected: 1", "build-44\n", "build\u{0}", "é"] {
+        match client().set_correlation_id(id) {
+            Err(BintrayError::Io(ref error)) => {
+                assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+            }
+            _ => panic!("Expected an InvalidInput error for {:?}", id),
+        }
+    }
+    for name in &["", "X-Id\r\nX-Injected", "X Id", "X-Id:"] {
+        assert!(client().set_correlation_header(name).is_err());
+    }
+    assert_eq!(transport.get_requests().len(), 3);
+}
+
+#[test]
+fn client_send() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/stats/my-company/my-repo",
+            MockResponse::new(200, r#"{"downloads": 42}"#))
+        .on(Method::Get, "/stats/my-company/unknown",
+            MockResponse::new(404, r#"{"message": "Repo not found"}"#))
+        .on(Method::Post, "/stats/my-company/my-repo",
+            MockResponse::new(409, r#"{"message": "Already there"}"#));
+    let client = BintrayClient::mock(None, None, transport);
+
+    let url = |repository: &str| {
+        let mut url = client.get_base_url();
+        url.path_segments_mut().unwrap()
+            .extend(&["stats", "my-company", repository]);
+        url
+    };
+
+    let mut resp = client.send(client.get(url("my-repo"))).unwrap();
+    let mut body = String::new();
+    resp.read_to_string(&mut body).unwrap();
+    assert_eq!(body, r#"{"downloads": 42}"#);
+
+    match client.send(client.get(url("unknown"))) {
+        Err(BintrayError::Io(ref error)) => {
+            assert_eq!(error.kind(), io::ErrorKind::NotFound);
+            assert!(error.to_string().contains("Repo not found"));
+        }
+        result => panic!("Expected a NotFound error, got {:?}", result.err()),
+    }
+
+    match client.send(client.post(url("my-repo"))) {
+        Err(BintrayError::Conflict { object, message }) => {
+            assert_eq!(object, "POST /stats/my-company/my-repo");
+            assert_eq!(message, "Already there");
+        }
+        result => panic!("Expected a Conflict error, got {:?}", result.err()),
+    }
+}
+
+#[test]
+fn copy_version_files() {
+    let sha1 = "f572d396fae9206628714fb2ce00f72e94f2258f";
+    let transport = MockTransport::new()
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0/files?include_unpublished=1",
+            MockResponse::new(200, &files_json(&[
+                FileJson::new("1.0.0", "1.0.0/a.txt").set_size(6).set_sha1(sha1),
+                FileJson::new("1.0.0", "1.0.0/b.txt").set_size(6).set_sha1(sha1),
+            ])))
+        .on(Method::Get, "/my-company/my-repo/1.0.0/a.txt",
+            MockResponse::new(200, "hello\n"))
+        .on(Method::Put,
+            "/content/my-company/my-repo/my-package-copy/2.0.0/2.0.0/a.txt",
+            MockResponse::new(201, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    let report = version.copy_all_to(
+        "my-package-copy", "2.0.0",
+        |path| Path::new("2.0.0").join(path.strip_prefix("1.0.0").unwrap()),
+        &client).unwrap();
+
+    assert_eq!(report.copied, vec![PathBuf::from("2.0.0/a.txt")]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0.path, PathBuf::from("1.0.0/b.txt"));
+
+    let upload = transport.get_requests().into_iter()
+        .find(|request| request.method == Method::Put)
+        .unwrap();
+    assert_eq!(upload.body, b"hello\n");
+}
+
+#[test]
+fn service_status() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/", MockResponse::new(404, "{}"))
+        .on(Method::Get, "/", MockResponse::new(503, "Maintenance"))
+        .on(Method::Get, "/", MockResponse::new(502, "Bad Gateway"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    assert_eq!(client.service_status().unwrap().health, ServiceHealth::Up);
+    assert_eq!(client.service_status().unwrap().health,
+               ServiceHealth::Maintenance);
+    assert_eq!(client.service_status().unwrap().health,
+               ServiceHealth::Degraded);
+}
+
+#[test]
+fn server_info() {
+    let transport = MockTransport::new()
+        .on(Method::Get, "/", MockResponse::new(503, "Maintenance"))
+        .on(Method::Get, "/",
+            MockResponse::new(200, "{}")
+            .header("Server", "nginx")
+            .header("X-Bintray-Version", "1.2.3"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    assert!(client.server_info().is_err());
+    for _ in 0..2 {
+        let server_info = client.server_info().unwrap();
+        assert_eq!(server_info.server.as_ref().unwrap(), "nginx");
+        assert_eq!(server_info.version_header.as_ref().unwrap(), "1.2.3");
+    }
+    assert_eq!(transport.get_requests().len(), 2);
+}
+
+#[test]
+fn content_paths_are_percent_encoded() {
+    let transport = MockTransport::new()
+        .on(Method::Put,
+            "/content/my-company/my-repo/my-package/1.0.0/my%20dir/a+b%25%C3%A9.txt",
+            MockResponse::new(201, r#"{"message": "success"}"#))
+        .on(Method::Get, "/my-company/my-repo/my%20dir/a+b%25%C3%A9.txt",
+            MockResponse::new(200, "hello\n"));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("content_paths_are_percent_encoded");
+    let local_filename = test_dir.join("percent-encoding");
+    fs::write(&local_filename, "hello\n").unwrap();
+
+    let no_values: &[&str] = &[];
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "my dir/a+b%é.txt");
+    assert!(content.upload(&local_filename, false, false, false, None,
+                           no_values, no_values, no_values, &client)
+            .is_ok());
+    assert!(content.download(&local_filename, &client).is_ok());
+    assert_eq!(fs::read_to_string(&local_filename).unwrap(), "hello\n");
+}
+
+#[test]
+fn identifiers_are_percent_encoded() {
+    let version_json = r#"{
+        "owner": "my company", "repo": "my-repo", "package": "my/package",
+        "name": "1.0 beta", "created": "2017-03-01T12:34:56.789Z",
+        "updated": "2017-03-01T12:34:56.789Z"
+    }"#;
+    let versions = "/packages/my%20company/my-repo/my%2Fpackage/versions";
+    let transport = MockTransport::new()
+        .on(Method::Post, versions, MockResponse::new(201, version_json))
+        .on(Method::Get, &format!("{}/1.0%20beta", versions),
+            MockResponse::new(200, version_json))
+        .on(Method::Delete, &format!("{}/1.0%20beta", versions),
+            MockResponse::new(200, r#"{"message": "success"}"#));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let mut version = Version::new(
+        "my company", "my-repo", "my/package", "1.0 beta");
+    assert!(version.create(&client).is_ok());
+    assert!(version.get(false, &client).is_ok());
+    assert!(version.delete(&client).is_ok());
+
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 3);
+    assert!(String::from_utf8_lossy(&requests[0].body)
+            .contains(r#""name": "1.0 beta""#));
+}
+
+#[test]
+fn download_host_override() {
+    let transport = MockTransport::new()
+        .on(Method::Head, "/my-company/my-repo/a.txt",
+            MockResponse::new(200, ""));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let content = Content::new(
+        "my-company", "my-repo", "my-package", "1.0.0", "a.txt");
+    assert!(content.clone().set_dl_host("").is_err());
+    assert!(content.clone().set_dl_host("cdn.example.com/path").is_err());
+    assert!(content.clone().set_dl_host("user@cdn.example.com").is_err());
+
+    let content = content.set_dl_host("cdn.example.com:8443").unwrap();
+    assert!(content.exists(&client).unwrap());
+
+    let requests = transport.get_requests();
+    assert_eq!(requests[0].host, "cdn.example.com");
+}
+
+#[test]
+fn content_try_new_rejects_unsafe_paths() {
+    let try_new = |path: &str| {
+        Content::try_new("my-company", "my-repo", "my-package", "1.0.0", path)
+    };
+    assert_eq!(try_new("./dir/a.txt").unwrap().path,
+               PathBuf::from("dir/a.txt"));
+    for path in &["/dir/a.txt", "../a.txt", "dir/../../a.txt"] {
+        match try_new(path) {
+            Err(BintrayError::UnsafePath { path: ref unsafe_path }) => {
+                assert_eq!(unsafe_path, &PathBuf::from(path))
+            }
+            _ => panic!("Expected an UnsafePath error for {}", path),
+        }
+    }
+    assert!(Content::try_new(
+            "my company", "my-repo", "my-package", "1.0.0", "a.txt").is_err());
+}
+
+#[test]
+fn release_summary() {
+    let files = files_json(&[
+        FileJson::new("1.0.0", "b.deb").set_size(1000).set_sha1("bbbb"),
+        FileJson::new("1.0.0", "a.deb").set_size(2000).set_sha1("aaaa"),
+        FileJson::new("1.0.0", "a.deb.asc").set_size(10).set_sha1("cccc"),
+    ]);
+
+    let transport = MockTransport::new()
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0",
+            MockResponse::new(200, &version_json(
+                "1.0.0", r#""published": true"#)))
+        .on(Method::Get,
+            "/packages/my-company/my-repo/my-package/versions/1.0.0/files",
+            MockResponse::new(200, &files));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let version = Version::new(
+        "my-company", "my-repo", "my-package", "1.0.0");
+    let summary = version.summary(&client).unwrap();
+    assert_eq!(summary.to_string(),
+               "version: my-company/my-repo/my-package/1.0.0\n\
+                published: true\n\
+                files: 3\n\
+                total_size: 3010\n\
+                signed: false\n\
+                file: aaaa a.deb\n\
+                file: cccc a.deb.asc\n\
+                file: bbbb b.deb");
+}
+
+#[test]
+fn maven_artifact_exists_after_upload() {
+    let maven_path = "io/pivotal/bintray_rs/my-lib/1.0.0/my-lib-1.0.0.jar";
+    let transport = MockTransport::new()
+        .on(Method::Put,
+            &format!("/maven/my-company/my-maven-repo/my-lib/{};publish=1",
+                     maven_path),
+            MockResponse::new(201, r#"{"message": "success"}"#))
+        .on(Method::Head,
+            &format!("/my-company/my-maven-repo/{}", maven_path),
+            MockResponse::new(200, ""));
+    let client = BintrayClient::mock(None, None, transport.clone());
+
+    let test_dir = TestDir::new("maven_artifact_exists_after_upload");
+    let local_filename = test_dir.join("maven-exists.jar");
+    fs::write(&local_filename, "PK").unwrap();
+
+    let version = Version::new(
+        "my-company", "my-maven-repo", "my-lib", "1.0.0");
+    assert!(version.upload_maven(
+            "io.pivotal.bintray_rs", "my-lib", None, &local_filename, true,
+            &client).is_ok());
+
+    let mut content = Content::new(
+        "my-company", "my-maven-repo", "my-lib", "1.0.0",
+        RepositoryType::maven_path(
+            "io.pivotal.bintray_rs", "my-lib", "1.0.0", "my-lib-1.0.0.jar"));
+    content.repository_type = Some(RepositoryType::Maven);
+    assert!(content.exists(&client).unwrap());
+}
+
+#[test]
+fn wait_for_condition_times_out() {
+    let clock = MockClock::new();
+    let mut attempts = 0;
+    let result = utils::wait_for_condition_with_clock(
+        &clock, &"my-object", "WaitForTest", Duration::from_secs(5), || {
+            attempts += 1;
+            Ok(false)
+        });
+
+    match result {
+        Err(BintrayError::Io(ref error)) => {
+            assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        }
+        _ => panic!("Expected a timeout, got {:?}", result),
+    }
+    assert_eq!(attempts, 4);
+    assert_eq!(clock.get_sleeps(), vec![
+        Duration::from_secs(2), Duration::from_secs(2), Duration::from_secs(1),
+    ]);
+    assert_eq!(clock.elapsed(), Duration::from_secs(5));
+}
+
+#[test]
+fn wait_for_condition_stops_when_met() {
+    let clock = MockClock::new();
+    let mut attempts = 0;
+    assert!(utils::wait_for_condition_with_clock(
+        &clock, &"my-object", "WaitForTest", Duration::from_secs(60), || {
+            attempts += 1;
+            Ok(attempts == 3)
+        }).is_ok());
+
+    assert_eq!(attempts, 3);
+    assert_eq!(clock.elapsed(), Duration::from_secs(4));
+}